@@ -7,6 +7,7 @@ pub const MAGENTA: Color = Color::Magenta;
 pub const RED: Color = Color::Red;
 pub const WHITE: Color = Color::White;
 pub const GREEN: Color = Color::Green;
+pub const YELLOW: Color = Color::Yellow;
 
 // DAY PALETTE (WARMER
 pub const DAY_BROWN: Color = Color::Rgb {
@@ -75,6 +76,18 @@ pub const DAY_WHITE: Color = Color::Rgb {
     b: 235,
 };
 
+pub const DAY_PURPLE: Color = Color::Rgb {
+    r: 170,
+    g: 110,
+    b: 220,
+};
+
+pub const DAY_RED: Color = Color::Rgb {
+    r: 210,
+    g: 70,
+    b: 50,
+};
+
 // NIGHT PALETTE
 pub const NIGHT_BROWN: Color = Color::Rgb {
     r: 105,
@@ -141,3 +154,15 @@ pub const NIGHT_WHITE: Color = Color::Rgb {
     g: 195,
     b: 215,
 };
+
+pub const NIGHT_PURPLE: Color = Color::Rgb {
+    r: 120,
+    g: 80,
+    b: 170,
+};
+
+pub const NIGHT_RED: Color = Color::Rgb {
+    r: 150,
+    g: 50,
+    b: 45,
+};
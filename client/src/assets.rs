@@ -107,6 +107,21 @@ impl FacilityAsset {
             (FacilityType::Barracks, true) => NIGHT_BARRACKS,
             (FacilityType::Shipyard, false) => DAY_SHIPYARD,
             (FacilityType::Shipyard, true) => NIGHT_SHIPYARD,
+            (FacilityType::MageTower, false) => DAY_MAGE_TOWER,
+            (FacilityType::MageTower, true) => NIGHT_MAGE_TOWER,
+            (FacilityType::DragonRoost, false) => DAY_DRAGON_ROOST,
+            (FacilityType::DragonRoost, true) => NIGHT_DRAGON_ROOST,
+            (FacilityType::Warehouse, false) => DAY_WAREHOUSE,
+            (FacilityType::Warehouse, true) => NIGHT_WAREHOUSE,
+        }
+    }
+
+    // Tiled over the whole facility area while it is under construction
+    pub fn get_scaffolding(night: bool) -> &'static [&'static [TermCell]] {
+        if night {
+            NIGHT_SCAFFOLDING
+        } else {
+            DAY_SCAFFOLDING
         }
     }
 }
@@ -120,10 +135,20 @@ pub const CURSOR: &[&[TermCell]] = &[&[
     TermCell::new('<', WHITE, BLACK),
 ]];
 
+pub const WAYPOINT: &[&[TermCell]] = &[&[
+    TermCell::new('+', WHITE, BLACK),
+    TermCell::new('+', WHITE, BLACK),
+]];
+
 pub const SELECTION_TERMCELL: TermCell = TermCell::new('<', BLACK, WHITE);
 
+pub const PROGRESS_DONE_TERMCELL: TermCell = TermCell::new('█', GREEN, BLACK);
+pub const PROGRESS_LEFT_TERMCELL: TermCell = TermCell::new('░', WHITE, BLACK);
+
 pub const BKG_EL: TermCell = TermCell::new('.', WHITE, BLACK);
 pub const COURTYARD_BK_CELL: TermCell = TermCell::new('.', DAY_GREEN_0, BLACK);
+// Outside the area unlocked by the castle level
+pub const COURTYARD_LOCKED_CELL: TermCell = TermCell::new('.', DAY_GREY_0, BLACK);
 
 // Tiles
 
@@ -204,22 +229,22 @@ pub struct GameObjAsset;
 impl GameObjAsset {
     pub fn get_asset(obj: &GameObjE, owned: bool) -> &[&[TermCell]] {
         match obj {
-            GameObjE::Castle(castle) => {
-                if !castle.alive {
-                    DEAD_CASTLE_ART
-                } else if owned {
-                    MY_CASTLE_ART
-                } else {
-                    CASTLE_ART
-                }
-            }
-            GameObjE::DeployedUnits(_) => {
-                if owned {
-                    MY_DEPLOYED_UNITS_ART
-                } else {
-                    DEPLOYED_UNITS_ART
-                }
-            }
+            // Castles grow into keeps and then citadels
+            GameObjE::Castle(castle) => match (castle.alive, castle.lv, owned) {
+                (false, _, _) => DEAD_CASTLE_ART,
+                (true, 0..=2, true) => MY_CASTLE_ART,
+                (true, 0..=2, false) => CASTLE_ART,
+                (true, 3..=4, true) => MY_KEEP_ART,
+                (true, 3..=4, false) => KEEP_ART,
+                (true, _, true) => MY_CITADEL_ART,
+                (true, _, false) => CITADEL_ART,
+            },
+            GameObjE::DeployedUnits(units) => match (units.embarked, owned) {
+                (false, true) => MY_DEPLOYED_UNITS_ART,
+                (false, false) => DEPLOYED_UNITS_ART,
+                (true, true) => MY_FLEET_ART,
+                (true, false) => FLEET_ART,
+            },
             _ => ERR_ART,
         }
     }
@@ -227,10 +252,16 @@ impl GameObjAsset {
 
 pub const MY_CASTLE_ART: &[&[TermCell]] = &[&[TermCell::new('@', GREEN, BLACK)]];
 pub const CASTLE_ART: &[&[TermCell]] = &[&[TermCell::new('@', WHITE, BLACK)]];
+pub const MY_KEEP_ART: &[&[TermCell]] = &[&[TermCell::new('A', GREEN, BLACK)]];
+pub const KEEP_ART: &[&[TermCell]] = &[&[TermCell::new('A', WHITE, BLACK)]];
+pub const MY_CITADEL_ART: &[&[TermCell]] = &[&[TermCell::new('W', GREEN, BLACK)]];
+pub const CITADEL_ART: &[&[TermCell]] = &[&[TermCell::new('W', WHITE, BLACK)]];
 pub const DEAD_CASTLE_ART: &[&[TermCell]] = &[&[TermCell::new('X', RED, BLACK)]];
 
 pub const MY_DEPLOYED_UNITS_ART: &[&[TermCell]] = &[&[TermCell::new('u', GREEN, BLACK)]];
 pub const DEPLOYED_UNITS_ART: &[&[TermCell]] = &[&[TermCell::new('u', WHITE, BLACK)]];
+pub const MY_FLEET_ART: &[&[TermCell]] = &[&[TermCell::new('b', GREEN, BLACK)]];
+pub const FLEET_ART: &[&[TermCell]] = &[&[TermCell::new('b', WHITE, BLACK)]];
 
 pub const ERR_ART: &[&[TermCell]] = &[&[TermCell::ERR]];
 
@@ -647,3 +678,286 @@ pub const NIGHT_SHIPYARD: &[&[TermCell]] = &[
         TermCell::new('~', NIGHT_BLUE_0, NIGHT_BLUE_1),
     ],
 ];
+
+// /^*^*^\
+// |◊▓▒▓◊|
+// |▒▓◊▓▒|
+// +--∩--+
+pub const DAY_MAGE_TOWER: &[&[TermCell]] = &[
+    &[
+        TermCell::new('/', DAY_GREY_2, BLACK),
+        TermCell::new('^', DAY_GREY_0, BLACK),
+        TermCell::new('*', DAY_PURPLE, BLACK),
+        TermCell::new('^', DAY_GREY_0, BLACK),
+        TermCell::new('*', DAY_PURPLE, BLACK),
+        TermCell::new('^', DAY_GREY_0, BLACK),
+        TermCell::new('\\', DAY_GREY_2, BLACK),
+    ],
+    &[
+        TermCell::new('|', DAY_GREY_2, BLACK),
+        TermCell::new('◊', DAY_PURPLE, DAY_GREY_2),
+        TermCell::new('▓', DAY_GREY_0, DAY_GREY_2),
+        TermCell::new('▒', DAY_GREY_0, DAY_GREY_2),
+        TermCell::new('▓', DAY_GREY_0, DAY_GREY_2),
+        TermCell::new('◊', DAY_PURPLE, DAY_GREY_2),
+        TermCell::new('|', DAY_GREY_2, BLACK),
+    ],
+    &[
+        TermCell::new('|', DAY_GREY_2, BLACK),
+        TermCell::new('▒', DAY_GREY_0, DAY_GREY_2),
+        TermCell::new('▓', DAY_GREY_0, DAY_GREY_2),
+        TermCell::new('◊', DAY_PURPLE, DAY_GREY_2),
+        TermCell::new('▓', DAY_GREY_0, DAY_GREY_2),
+        TermCell::new('▒', DAY_GREY_0, DAY_GREY_2),
+        TermCell::new('|', DAY_GREY_2, BLACK),
+    ],
+    &[
+        TermCell::new('+', DAY_GREY_2, BLACK),
+        TermCell::new('-', DAY_GREY_2, BLACK),
+        TermCell::new('-', DAY_GREY_2, BLACK),
+        TermCell::new('∩', DAY_PURPLE, BLACK),
+        TermCell::new('-', DAY_GREY_2, BLACK),
+        TermCell::new('-', DAY_GREY_2, BLACK),
+        TermCell::new('+', DAY_GREY_2, BLACK),
+    ],
+];
+
+pub const NIGHT_MAGE_TOWER: &[&[TermCell]] = &[
+    &[
+        TermCell::new('/', NIGHT_GREY_2, BLACK),
+        TermCell::new('^', NIGHT_GREY_0, BLACK),
+        TermCell::new('*', NIGHT_PURPLE, BLACK),
+        TermCell::new('^', NIGHT_GREY_0, BLACK),
+        TermCell::new('*', NIGHT_PURPLE, BLACK),
+        TermCell::new('^', NIGHT_GREY_0, BLACK),
+        TermCell::new('\\', NIGHT_GREY_2, BLACK),
+    ],
+    &[
+        TermCell::new('|', NIGHT_GREY_2, BLACK),
+        TermCell::new('◊', NIGHT_PURPLE, NIGHT_GREY_2),
+        TermCell::new('▓', NIGHT_GREY_0, NIGHT_GREY_2),
+        TermCell::new('▒', NIGHT_GREY_0, NIGHT_GREY_2),
+        TermCell::new('▓', NIGHT_GREY_0, NIGHT_GREY_2),
+        TermCell::new('◊', NIGHT_PURPLE, NIGHT_GREY_2),
+        TermCell::new('|', NIGHT_GREY_2, BLACK),
+    ],
+    &[
+        TermCell::new('|', NIGHT_GREY_2, BLACK),
+        TermCell::new('▒', NIGHT_GREY_0, NIGHT_GREY_2),
+        TermCell::new('▓', NIGHT_GREY_0, NIGHT_GREY_2),
+        TermCell::new('◊', NIGHT_PURPLE, NIGHT_GREY_2),
+        TermCell::new('▓', NIGHT_GREY_0, NIGHT_GREY_2),
+        TermCell::new('▒', NIGHT_GREY_0, NIGHT_GREY_2),
+        TermCell::new('|', NIGHT_GREY_2, BLACK),
+    ],
+    &[
+        TermCell::new('+', NIGHT_GREY_2, BLACK),
+        TermCell::new('-', NIGHT_GREY_2, BLACK),
+        TermCell::new('-', NIGHT_GREY_2, BLACK),
+        TermCell::new('∩', NIGHT_PURPLE, BLACK),
+        TermCell::new('-', NIGHT_GREY_2, BLACK),
+        TermCell::new('-', NIGHT_GREY_2, BLACK),
+        TermCell::new('+', NIGHT_GREY_2, BLACK),
+    ],
+];
+
+// /\^^^^^^^/\
+// |▓▒▓▒§▒▓▒▓|
+// |░░▓▓▓▓▓░░|
+// +---/▀\---+
+pub const DAY_DRAGON_ROOST: &[&[TermCell]] = &[
+    &[
+        TermCell::new('/', DAY_BROWN, BLACK),
+        TermCell::new('\\', DAY_BROWN, BLACK),
+        TermCell::new('^', DAY_RED, BLACK),
+        TermCell::new('^', DAY_RED, BLACK),
+        TermCell::new('^', DAY_RED, BLACK),
+        TermCell::new('^', DAY_RED, BLACK),
+        TermCell::new('^', DAY_RED, BLACK),
+        TermCell::new('^', DAY_RED, BLACK),
+        TermCell::new('^', DAY_RED, BLACK),
+        TermCell::new('/', DAY_BROWN, BLACK),
+        TermCell::new('\\', DAY_BROWN, BLACK),
+    ],
+    &[
+        TermCell::new('|', DAY_BROWN, BLACK),
+        TermCell::new('▓', DAY_GREY_1, DAY_GREY_2),
+        TermCell::new('▒', DAY_GREY_1, DAY_GREY_2),
+        TermCell::new('▓', DAY_GREY_1, DAY_GREY_2),
+        TermCell::new('▒', DAY_GREY_1, DAY_GREY_2),
+        TermCell::new('§', DAY_RED, DAY_GREY_2),
+        TermCell::new('▒', DAY_GREY_1, DAY_GREY_2),
+        TermCell::new('▓', DAY_GREY_1, DAY_GREY_2),
+        TermCell::new('▒', DAY_GREY_1, DAY_GREY_2),
+        TermCell::new('▓', DAY_GREY_1, DAY_GREY_2),
+        TermCell::new('|', DAY_BROWN, BLACK),
+    ],
+    &[
+        TermCell::new('|', DAY_BROWN, BLACK),
+        TermCell::new('░', DAY_GREY_1, DAY_GREY_2),
+        TermCell::new('░', DAY_GREY_1, DAY_GREY_2),
+        TermCell::new('▓', DAY_GREY_1, DAY_GREY_2),
+        TermCell::new('▓', DAY_GREY_1, DAY_GREY_2),
+        TermCell::new('▓', DAY_GREY_1, DAY_GREY_2),
+        TermCell::new('▓', DAY_GREY_1, DAY_GREY_2),
+        TermCell::new('▓', DAY_GREY_1, DAY_GREY_2),
+        TermCell::new('░', DAY_GREY_1, DAY_GREY_2),
+        TermCell::new('░', DAY_GREY_1, DAY_GREY_2),
+        TermCell::new('|', DAY_BROWN, BLACK),
+    ],
+    &[
+        TermCell::new('+', DAY_BROWN, BLACK),
+        TermCell::new('-', DAY_BROWN, BLACK),
+        TermCell::new('-', DAY_BROWN, BLACK),
+        TermCell::new('-', DAY_BROWN, BLACK),
+        TermCell::new('/', DAY_BROWN, BLACK),
+        TermCell::new('▀', DAY_RED, BLACK),
+        TermCell::new('\\', DAY_BROWN, BLACK),
+        TermCell::new('-', DAY_BROWN, BLACK),
+        TermCell::new('-', DAY_BROWN, BLACK),
+        TermCell::new('-', DAY_BROWN, BLACK),
+        TermCell::new('+', DAY_BROWN, BLACK),
+    ],
+];
+
+pub const NIGHT_DRAGON_ROOST: &[&[TermCell]] = &[
+    &[
+        TermCell::new('/', NIGHT_BROWN, BLACK),
+        TermCell::new('\\', NIGHT_BROWN, BLACK),
+        TermCell::new('^', NIGHT_RED, BLACK),
+        TermCell::new('^', NIGHT_RED, BLACK),
+        TermCell::new('^', NIGHT_RED, BLACK),
+        TermCell::new('^', NIGHT_RED, BLACK),
+        TermCell::new('^', NIGHT_RED, BLACK),
+        TermCell::new('^', NIGHT_RED, BLACK),
+        TermCell::new('^', NIGHT_RED, BLACK),
+        TermCell::new('/', NIGHT_BROWN, BLACK),
+        TermCell::new('\\', NIGHT_BROWN, BLACK),
+    ],
+    &[
+        TermCell::new('|', NIGHT_BROWN, BLACK),
+        TermCell::new('▓', NIGHT_GREY_1, NIGHT_GREY_2),
+        TermCell::new('▒', NIGHT_GREY_1, NIGHT_GREY_2),
+        TermCell::new('▓', NIGHT_GREY_1, NIGHT_GREY_2),
+        TermCell::new('▒', NIGHT_GREY_1, NIGHT_GREY_2),
+        TermCell::new('§', NIGHT_RED, NIGHT_GREY_2),
+        TermCell::new('▒', NIGHT_GREY_1, NIGHT_GREY_2),
+        TermCell::new('▓', NIGHT_GREY_1, NIGHT_GREY_2),
+        TermCell::new('▒', NIGHT_GREY_1, NIGHT_GREY_2),
+        TermCell::new('▓', NIGHT_GREY_1, NIGHT_GREY_2),
+        TermCell::new('|', NIGHT_BROWN, BLACK),
+    ],
+    &[
+        TermCell::new('|', NIGHT_BROWN, BLACK),
+        TermCell::new('░', NIGHT_GREY_1, NIGHT_GREY_2),
+        TermCell::new('░', NIGHT_GREY_1, NIGHT_GREY_2),
+        TermCell::new('▓', NIGHT_GREY_1, NIGHT_GREY_2),
+        TermCell::new('▓', NIGHT_GREY_1, NIGHT_GREY_2),
+        TermCell::new('▓', NIGHT_GREY_1, NIGHT_GREY_2),
+        TermCell::new('▓', NIGHT_GREY_1, NIGHT_GREY_2),
+        TermCell::new('▓', NIGHT_GREY_1, NIGHT_GREY_2),
+        TermCell::new('░', NIGHT_GREY_1, NIGHT_GREY_2),
+        TermCell::new('░', NIGHT_GREY_1, NIGHT_GREY_2),
+        TermCell::new('|', NIGHT_BROWN, BLACK),
+    ],
+    &[
+        TermCell::new('+', NIGHT_BROWN, BLACK),
+        TermCell::new('-', NIGHT_BROWN, BLACK),
+        TermCell::new('-', NIGHT_BROWN, BLACK),
+        TermCell::new('-', NIGHT_BROWN, BLACK),
+        TermCell::new('/', NIGHT_BROWN, BLACK),
+        TermCell::new('▀', NIGHT_RED, BLACK),
+        TermCell::new('\\', NIGHT_BROWN, BLACK),
+        TermCell::new('-', NIGHT_BROWN, BLACK),
+        TermCell::new('-', NIGHT_BROWN, BLACK),
+        TermCell::new('-', NIGHT_BROWN, BLACK),
+        TermCell::new('+', NIGHT_BROWN, BLACK),
+    ],
+];
+
+// /=====\
+// |▒#▒#▒|
+// +-[]--+
+pub const DAY_WAREHOUSE: &[&[TermCell]] = &[
+    &[
+        TermCell::new('/', DAY_BROWN, BLACK),
+        TermCell::new('=', DAY_GREY_1, BLACK),
+        TermCell::new('=', DAY_GREY_1, BLACK),
+        TermCell::new('=', DAY_GREY_1, BLACK),
+        TermCell::new('=', DAY_GREY_1, BLACK),
+        TermCell::new('=', DAY_GREY_1, BLACK),
+        TermCell::new('\\', DAY_BROWN, BLACK),
+    ],
+    &[
+        TermCell::new('|', DAY_BROWN, BLACK),
+        TermCell::new('▒', DAY_BROWN, DAY_GREY_2),
+        TermCell::new('#', DAY_GREY_0, DAY_GREY_2),
+        TermCell::new('▒', DAY_BROWN, DAY_GREY_2),
+        TermCell::new('#', DAY_GREY_0, DAY_GREY_2),
+        TermCell::new('▒', DAY_BROWN, DAY_GREY_2),
+        TermCell::new('|', DAY_BROWN, BLACK),
+    ],
+    &[
+        TermCell::new('+', DAY_BROWN, BLACK),
+        TermCell::new('-', DAY_BROWN, BLACK),
+        TermCell::new('[', DAY_GREY_0, BLACK),
+        TermCell::new(']', DAY_GREY_0, BLACK),
+        TermCell::new('-', DAY_BROWN, BLACK),
+        TermCell::new('-', DAY_BROWN, BLACK),
+        TermCell::new('+', DAY_BROWN, BLACK),
+    ],
+];
+
+pub const NIGHT_WAREHOUSE: &[&[TermCell]] = &[
+    &[
+        TermCell::new('/', NIGHT_BROWN, BLACK),
+        TermCell::new('=', NIGHT_GREY_1, BLACK),
+        TermCell::new('=', NIGHT_GREY_1, BLACK),
+        TermCell::new('=', NIGHT_GREY_1, BLACK),
+        TermCell::new('=', NIGHT_GREY_1, BLACK),
+        TermCell::new('=', NIGHT_GREY_1, BLACK),
+        TermCell::new('\\', NIGHT_BROWN, BLACK),
+    ],
+    &[
+        TermCell::new('|', NIGHT_BROWN, BLACK),
+        TermCell::new('▒', NIGHT_BROWN, NIGHT_GREY_2),
+        TermCell::new('#', NIGHT_GREY_0, NIGHT_GREY_2),
+        TermCell::new('▒', NIGHT_BROWN, NIGHT_GREY_2),
+        TermCell::new('#', NIGHT_GREY_0, NIGHT_GREY_2),
+        TermCell::new('▒', NIGHT_BROWN, NIGHT_GREY_2),
+        TermCell::new('|', NIGHT_BROWN, BLACK),
+    ],
+    &[
+        TermCell::new('+', NIGHT_BROWN, BLACK),
+        TermCell::new('-', NIGHT_BROWN, BLACK),
+        TermCell::new('[', NIGHT_GREY_0, BLACK),
+        TermCell::new(']', NIGHT_GREY_0, BLACK),
+        TermCell::new('-', NIGHT_BROWN, BLACK),
+        TermCell::new('-', NIGHT_BROWN, BLACK),
+        TermCell::new('+', NIGHT_BROWN, BLACK),
+    ],
+];
+
+// #=
+// |#
+pub const DAY_SCAFFOLDING: &[&[TermCell]] = &[
+    &[
+        TermCell::new('#', DAY_BROWN, BLACK),
+        TermCell::new('=', DAY_GREY_1, BLACK),
+    ],
+    &[
+        TermCell::new('|', DAY_GREY_1, BLACK),
+        TermCell::new('#', DAY_BROWN, BLACK),
+    ],
+];
+
+pub const NIGHT_SCAFFOLDING: &[&[TermCell]] = &[
+    &[
+        TermCell::new('#', NIGHT_BROWN, BLACK),
+        TermCell::new('=', NIGHT_GREY_1, BLACK),
+    ],
+    &[
+        TermCell::new('|', NIGHT_GREY_1, BLACK),
+        TermCell::new('#', NIGHT_BROWN, BLACK),
+    ],
+];
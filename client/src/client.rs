@@ -1,4 +1,4 @@
-use std::sync::Arc;
+use std::{env, sync::Arc};
 use tokio::{
     io::BufReader,
     net::TcpStream,
@@ -9,7 +9,7 @@ use crate::connection::Connection;
 use crate::shutdown::ShutdownChannel;
 use crate::tui::Tui;
 use common::{
-    r#const::{IP_LOCAL, ONLINE},
+    r#const::{ADMIN_TOKEN_VAR, IP_LOCAL, ONLINE},
     packets::C2S,
     stream,
 };
@@ -39,7 +39,9 @@ impl Client {
 
         println!("Connection established. Please log in.");
         let name = Tui::login();
-        stream::send_msg_to_server(&mut writer, &C2S::Login(name))
+        // Only needed for the admin commands
+        let admin_token = env::var(ADMIN_TOKEN_VAR).ok();
+        stream::send_msg_to_server(&mut writer, &C2S::Login(name, admin_token))
             .await
             .unwrap();
 
@@ -51,6 +53,7 @@ impl Client {
         let mut connection = Connection {
             writer,
             reader: BufReader::new(reader),
+            next_request_id: 0,
         };
 
         println!("Fetching initial game state...");
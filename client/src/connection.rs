@@ -1,8 +1,11 @@
 use std::sync::Arc;
 
 use common::{
-    packets::{C2S, C2S4L, L2S4C, LogE, S2C},
+    packets::{
+        BattleOutcome, C2S, C2S4L, EventE, L2S4C, Rejection, RequestId, RouteErr, S2C, Severity,
+    },
     stream::{StreamErr, get_msg_from_server, send_msg_to_server},
+    units::UnitGroup,
 };
 use tokio::{
     io::BufReader,
@@ -11,7 +14,7 @@ use tokio::{
 };
 
 use crate::{
-    game_state::GameState,
+    game_state::{GameState, RequestOrigin},
     shutdown::{ShutdownChannel, ShutdownReason},
     tui::T2C,
 };
@@ -19,6 +22,7 @@ use crate::{
 pub struct Connection {
     pub writer: OwnedWriteHalf,
     pub reader: BufReader<OwnedReadHalf>,
+    pub next_request_id: RequestId,
 }
 
 impl Connection {
@@ -35,7 +39,15 @@ impl Connection {
 
             tokio::select! {
                 Some(msg_from_tui) = t2c_rx.recv() => {
-                    let msg = C2S::C2S4L(t2c_to_c2s4l(msg_from_tui));
+                    let request_id = self.next_request_id;
+                    self.next_request_id = self.next_request_id.wrapping_add(1);
+                    game_state.lock().await.add_pending_request(
+                        request_id,
+                        t2c_description(&msg_from_tui),
+                        t2c_origin(&msg_from_tui),
+                    );
+
+                    let msg = C2S::C2S4L(request_id, t2c_to_c2s4l(msg_from_tui));
                     let _ = send_msg_to_server(&mut self.writer, &msg).await;
                 },
 
@@ -90,10 +102,118 @@ fn t2c_to_c2s4l(msg: T2C) -> C2S4L {
     match msg {
         T2C::NewCastle(pos) => C2S4L::NewCastle(pos),
         T2C::AttackCastle(target_id, units) => C2S4L::AttackCastle(target_id, units),
-        T2C::SendUnits(target_pos, units) => C2S4L::SendUnits(target_pos, units),
+        T2C::SendUnits(waypoints, units, patrol) => C2S4L::SendUnits(waypoints, units, patrol),
+        T2C::RecallUnits(units_id) => C2S4L::RecallUnits(units_id),
+        T2C::RedirectUnits(units_id, target_pos) => C2S4L::RedirectUnits(units_id, target_pos),
+        T2C::RedirectAttack(units_id, target_id) => C2S4L::RedirectAttack(units_id, target_id),
+        T2C::MergeUnits(units_id, into_id) => C2S4L::MergeUnits(units_id, into_id),
+        T2C::SplitUnits(units_id, units) => C2S4L::SplitUnits(units_id, units),
+        T2C::ReinforceCastle(castle_id, units) => C2S4L::ReinforceCastle(castle_id, units),
+        T2C::RecallReinforcements(castle_id) => C2S4L::RecallReinforcements(castle_id),
+        T2C::GiftResources(castle_id, resources) => C2S4L::GiftResources(castle_id, resources),
         T2C::InCourtyard => C2S4L::InCourtyard,
         T2C::OutCourtyard => C2S4L::OutCourtyard,
         T2C::NewFacility(payload) => C2S4L::NewFacility(payload),
+        T2C::UpgradeFacility(facility_id) => C2S4L::UpgradeFacility(facility_id),
+        T2C::DemolishFacility(facility_id) => C2S4L::DemolishFacility(facility_id),
+        T2C::RelocateFacility(facility_id, pos) => C2S4L::RelocateFacility(facility_id, pos),
+        T2C::AssignWorkers(facility_id, workers) => C2S4L::AssignWorkers(facility_id, workers),
+        T2C::UpgradeCastle => C2S4L::UpgradeCastle,
+        T2C::Research(tech) => C2S4L::Research(tech),
+        T2C::TrainUnits(unit, count) => C2S4L::TrainUnits(unit, count),
+        T2C::CancelTraining(job_id) => C2S4L::CancelTraining(job_id),
+        T2C::GrantKit(castle_id, kit) => C2S4L::GrantKit(castle_id, kit),
+    }
+}
+
+fn t2c_description(msg: &T2C) -> &'static str {
+    match msg {
+        T2C::NewCastle(_) => "Build castle",
+        T2C::AttackCastle(_, _) => "Attack",
+        T2C::SendUnits(_, _, _) => "Send units",
+        T2C::RecallUnits(_) => "Recall units",
+        T2C::RedirectUnits(_, _) | T2C::RedirectAttack(_, _) => "Redirect units",
+        T2C::MergeUnits(_, _) => "Merge units",
+        T2C::SplitUnits(_, _) => "Split units",
+        T2C::ReinforceCastle(_, _) => "Reinforce",
+        T2C::RecallReinforcements(_) => "Recall reinforcements",
+        T2C::GiftResources(_, _) => "Gift resources",
+        T2C::InCourtyard => "Enter courtyard",
+        T2C::OutCourtyard => "Leave courtyard",
+        T2C::NewFacility(_) => "Build facility",
+        T2C::UpgradeFacility(_) => "Upgrade facility",
+        T2C::DemolishFacility(_) => "Demolish facility",
+        T2C::RelocateFacility(_, _) => "Relocate facility",
+        T2C::AssignWorkers(_, _) => "Assign workers",
+        T2C::UpgradeCastle => "Upgrade castle",
+        T2C::Research(_) => "Research",
+        T2C::TrainUnits(_, _) => "Train units",
+        T2C::CancelTraining(_) => "Cancel training",
+        T2C::GrantKit(_, _) => "Grant kit",
+    }
+}
+
+fn t2c_origin(msg: &T2C) -> RequestOrigin {
+    match msg {
+        T2C::NewFacility(_)
+        | T2C::UpgradeFacility(_)
+        | T2C::DemolishFacility(_)
+        | T2C::RelocateFacility(_, _)
+        | T2C::AssignWorkers(_, _)
+        | T2C::TrainUnits(_, _)
+        | T2C::CancelTraining(_)
+        | T2C::OutCourtyard => RequestOrigin::Courtyard,
+        T2C::Research(_) => RequestOrigin::Research,
+        T2C::NewCastle(_)
+        | T2C::AttackCastle(_, _)
+        | T2C::SendUnits(_, _, _)
+        | T2C::RecallUnits(_)
+        | T2C::RedirectUnits(_, _)
+        | T2C::RedirectAttack(_, _)
+        | T2C::MergeUnits(_, _)
+        | T2C::SplitUnits(_, _)
+        | T2C::ReinforceCastle(_, _)
+        | T2C::RecallReinforcements(_)
+        | T2C::GiftResources(_, _)
+        | T2C::InCourtyard
+        | T2C::UpgradeCastle
+        | T2C::GrantKit(_, _) => RequestOrigin::Map,
+    }
+}
+
+fn rejection_to_string(rejection: Rejection) -> &'static str {
+    match rejection {
+        Rejection::NoCastle => "you have no castle",
+        Rejection::AlreadyHasCastle => "you already have a castle",
+        Rejection::NotEvenCoord => "castles need even coordinates",
+        Rejection::OccupiedPos => "the position is occupied",
+        Rejection::OutOfBounds => "out of bounds",
+        Rejection::NotEnoughResources => "not enough resources",
+        Rejection::NotEnoughUnits => "not enough units",
+        Rejection::MaxFacilitiesReached => "max facilities of this type reached",
+        Rejection::MaxLevelReached => "max level reached",
+        Rejection::MaxWorkersReached => "the facility can't employ more workers",
+        Rejection::FacilityLocked => "your castle level is too low or a tech is missing",
+        Rejection::AlreadyResearched => "the tech is already researched or queued",
+        Rejection::MissingPrerequisite => "research the prerequisites first",
+        Rejection::ResearchQueueFull => "the research queue is full",
+        Rejection::AlreadyBuilding => "the facility is already being built",
+        Rejection::BuildQueueFull => "the build queue is full",
+        Rejection::MissingFacility => "no facility can train these units",
+        Rejection::NotEnoughPeasants => "not enough peasants",
+        Rejection::TrainingQueueFull => "the training queue is full",
+        Rejection::NotAdmin => "only admins can do this",
+        Rejection::UnknownKit => "no kit with this name",
+        Rejection::InvalidTarget => "invalid target",
+        Rejection::NotYourUnits => "these are not your units",
+        Rejection::UnitsNotDeployed => "the units are still planning their route",
+        Rejection::TooFar => "the units are too far apart",
+        Rejection::CannotSail => "not enough ships to carry the units",
+        Rejection::TooManyWaypoints => "too many waypoints",
+        Rejection::NothingToRecall => "nothing to recall",
+        Rejection::Route(RouteErr::Unreachable) => "the destination is unreachable",
+        Rejection::Route(RouteErr::TargetObstacle) => "the destination is an obstacle",
+        Rejection::Route(RouteErr::Timeout) => "it took too long to find a path",
     }
 }
 
@@ -107,6 +227,9 @@ fn handle_server_msg(msg: S2C, game_state: &mut GameState, shutdown: &ShutdownCh
         }
         S2C::L2S4C(L2S4C::CourtyardPacket(packet)) => {
             game_state.facilities = packet.facilities;
+            game_state.build_queue = packet.build_queue;
+            game_state.training_queue = packet.training_queue;
+            game_state.peasants = packet.peasants;
             game_state.castle = Some(packet.castle);
             game_state.player = packet.player;
             game_state.time = packet.time;
@@ -114,14 +237,122 @@ fn handle_server_msg(msg: S2C, game_state: &mut GameState, shutdown: &ShutdownCh
         S2C::L2S4C(L2S4C::Map(payload)) => {
             game_state.map = payload.unflatten();
         }
-        S2C::L2S4C(L2S4C::Log(log)) => {
-            let string = match log {
-                LogE::CastleCreationErr => "Could not create castle".to_string(),
-                LogE::UnitDeployErr => "Could not deploy units".to_string(),
-                LogE::AttackDeployErr => "Could not attack ziocan".to_string(),
-                LogE::FacilityCreationErr => "Could not create new facility".to_string(),
+        S2C::L2S4C(L2S4C::Feedback(request_id, result)) => {
+            let pending = game_state.pending_requests.remove(&request_id);
+            if let Err(rejection) = result {
+                let description = pending
+                    .as_ref()
+                    .map_or("Request", |pending| pending.description);
+                let message = format!("{} failed: {}", description, rejection_to_string(rejection));
+                if let Some(pending) = pending {
+                    game_state
+                        .rejections
+                        .insert(pending.origin, message.clone());
+                }
+                game_state.add_log_with_severity(message, Severity::Warning);
+            }
+        }
+        S2C::L2S4C(L2S4C::Event(event)) => {
+            let severity = event.severity();
+            let string = match event {
+                EventE::RouteErr(err) => format!(
+                    "No route for the units: {}",
+                    rejection_to_string(Rejection::Route(err))
+                ),
+                EventE::ReinforcementsArrived { from, units } => {
+                    format!("Reinforcements from {}: {}", from, units_to_string(&units))
+                }
+                EventE::GiftReceived { from, resources } => {
+                    format!("{} sent you {}", from, resources)
+                }
+                EventE::FacilityBuilt { r#type, lv } => {
+                    if lv == 1 {
+                        format!("Your {:?} has been built", r#type)
+                    } else {
+                        format!("Your {:?} has been upgraded to lv {}", r#type, lv)
+                    }
+                }
+                EventE::UnitsTrained { unit, count } => {
+                    format!("{} {:?} finished their training", count, unit)
+                }
+                EventE::TechResearched { tech } => {
+                    format!("{:?} researched: {}", tech, tech.description())
+                }
+                EventE::ResourcesWasted { wasted } => {
+                    format!("Your storage is full, you wasted {}", wasted)
+                }
+                EventE::UpkeepUnpaid { deserters } => format!(
+                    "You can't pay your army: {} units deserted",
+                    deserters.quantities.iter().sum::<u32>()
+                ),
+                EventE::Starvation {
+                    peasants,
+                    deserters,
+                } => format!(
+                    "Your castle is starving: {} peasants died, {} units deserted",
+                    peasants,
+                    deserters.quantities.iter().sum::<u32>()
+                ),
+                EventE::FieldBattle {
+                    pos,
+                    outcome,
+                    losses,
+                    enemy_losses,
+                } => {
+                    let outcome = match outcome {
+                        BattleOutcome::Victory => "won",
+                        BattleOutcome::Retreat => "retreated",
+                        BattleOutcome::Defeat => "lost",
+                    };
+                    format!(
+                        "Battle at {}: {}. Lost: {}. Killed: {}",
+                        pos,
+                        outcome,
+                        units_to_string(&losses),
+                        units_to_string(&enemy_losses)
+                    )
+                }
+                EventE::AttackDispatched { attacker } => {
+                    format!("{} sent an army against your castle!", attacker)
+                }
+                EventE::AttackApproaching { attacker, distance } => {
+                    format!(
+                        "The army of {} is {} tiles from your castle!",
+                        attacker, distance
+                    )
+                }
+                EventE::CastleBattle {
+                    attacker,
+                    defender,
+                    outcome,
+                    losses,
+                    enemy_losses,
+                } => {
+                    let outcome = match outcome {
+                        BattleOutcome::Victory => "won",
+                        BattleOutcome::Retreat => "held off",
+                        BattleOutcome::Defeat => "lost",
+                    };
+                    format!(
+                        "Siege of {} by {}: {}. Lost: {}. Killed: {}",
+                        defender,
+                        attacker,
+                        outcome,
+                        units_to_string(&losses),
+                        units_to_string(&enemy_losses)
+                    )
+                }
+                EventE::Plundered { target, loot } => {
+                    format!("Your army plundered {} from {}", loot, target)
+                }
+                EventE::Pillaged { attacker, loot } => {
+                    format!("The army of {} carried off {}", attacker, loot)
+                }
+                EventE::LootDelivered { loot } => {
+                    format!("Your army came back home with {}", loot)
+                }
             };
-            game_state.add_log(string);
+            game_state.add_log_with_severity(string, severity);
         }
         S2C::ServerShutdown => {
             shutdown.shutdown(ShutdownReason::ServerShutdown);
@@ -137,3 +368,14 @@ fn handle_server_msg(msg: S2C, game_state: &mut GameState, shutdown: &ShutdownCh
         }
     }
 }
+
+fn units_to_string(units: &UnitGroup) -> String {
+    if units.is_empty() {
+        return "none".to_string();
+    }
+    units
+        .iter_present()
+        .map(|(unit, count)| format!("{} {:?}", count, unit))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
@@ -2,23 +2,45 @@ use std::collections::HashMap;
 
 use common::{
     GameCoord, GameId, Time,
-    courtyard::Facility,
+    courtyard::{BuildJob, Facility, TrainingJob},
     game_objs::{GameObjE, OwnedCastleE},
     map::Tile,
+    packets::{RequestId, Severity},
     player::PlayerE,
+    research::TechType,
 };
 
 use crate::r#const::LOGS_CAPACITY;
 use crate::logs::Logs;
 
+// The view a command was sent from, where its rejection is shown
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RequestOrigin {
+    Map,
+    Courtyard,
+    Research,
+}
+
+pub struct PendingRequest {
+    pub description: &'static str,
+    pub origin: RequestOrigin,
+}
+
 pub struct GameState {
     pub time: Time,
     pub map: Vec<Vec<Tile>>,
     pub player: PlayerE,
     pub castle: Option<OwnedCastleE>,
     pub facilities: HashMap<u8, Facility>,
+    pub build_queue: Vec<BuildJob>,
+    pub training_queue: Vec<TrainingJob>,
+    pub peasants: u32,
     pub objs: HashMap<GameId, GameObjE>,
     pub logs: Logs,
+    // Commands waiting for the server feedback
+    pub pending_requests: HashMap<RequestId, PendingRequest>,
+    // Last rejection of each view, until another command is sent from it
+    pub rejections: HashMap<RequestOrigin, String>,
 }
 
 impl GameState {
@@ -35,13 +57,42 @@ impl GameState {
             player,
             castle,
             facilities: HashMap::new(),
+            build_queue: Vec::new(),
+            training_queue: Vec::new(),
+            peasants: 0,
             objs,
             logs: Logs::new(LOGS_CAPACITY),
+            pending_requests: HashMap::new(),
+            rejections: HashMap::new(),
         }
     }
 
     pub fn add_log(&mut self, message: impl Into<String>) {
-        self.logs.add(message.into());
+        self.logs.add(message.into(), Severity::Info);
+    }
+
+    pub fn add_log_with_severity(&mut self, message: impl Into<String>, severity: Severity) {
+        self.logs.add(message.into(), severity);
+    }
+
+    pub fn add_pending_request(
+        &mut self,
+        request_id: RequestId,
+        description: &'static str,
+        origin: RequestOrigin,
+    ) {
+        self.rejections.remove(&origin);
+        self.pending_requests.insert(
+            request_id,
+            PendingRequest {
+                description,
+                origin,
+            },
+        );
+    }
+
+    pub fn get_rejection(&self, origin: RequestOrigin) -> Option<&str> {
+        self.rejections.get(&origin).map(String::as_str)
     }
 
     pub fn get_tile(&self, coord: GameCoord) -> Tile {
@@ -55,4 +106,30 @@ impl GameState {
     pub fn get_facility(&self, facility_id: u8) -> Option<&Facility> {
         self.facilities.get(&facility_id)
     }
+
+    // Training jobs of the given facility, in queue order
+    pub fn get_training_jobs(&self, facility_id: u8) -> Vec<&TrainingJob> {
+        let Some(facility) = self.get_facility(facility_id) else {
+            return Vec::new();
+        };
+        self.training_queue
+            .iter()
+            .filter(|job| job.unit.trained_at() == Some(facility.r#type))
+            .collect()
+    }
+
+    // Empty without a castle
+    pub fn get_researched(&self) -> &[TechType] {
+        self.castle
+            .as_ref()
+            .map_or(&[], |castle| castle.researched.as_slice())
+    }
+
+    // The job of the facility with its position in the build queue
+    pub fn get_build_job(&self, facility_id: u8) -> Option<(usize, &BuildJob)> {
+        self.build_queue
+            .iter()
+            .enumerate()
+            .find(|(_, job)| job.facility_id == facility_id)
+    }
 }
@@ -1,6 +1,8 @@
 use common::GameId;
 use common::courtyard::FacilityType;
 use common::game_objs::GameObjE;
+use common::research::TechType;
+use common::resources::ResourceType;
 use common::units::UnitType;
 use crossterm::event::{Event, KeyCode, KeyEventKind, KeyModifiers};
 use std::collections::HashMap;
@@ -12,9 +14,14 @@ use crate::game_state::GameState;
 use crate::renderer::ModPlayerInfoTab;
 use crate::shutdown::{ShutdownChannel, ShutdownReason};
 use crate::tui::{T2C, Tui};
-use crate::ui_state::{FacilitySelection, Inspect, InteractTarget, UiMode, UiState, UnitSelection};
+use crate::ui_state::{
+    FacilitySelection, Inspect, InteractTarget, KitGrant, ResearchSelection, ResourceSelection,
+    TrainingSelection, UiMode, UiState, UnitSelection,
+};
 use common::GameCoord;
-use common::r#const::{COURTYARD_COLS, COURTYARD_ROWS, MAP_COLS, MAP_ROWS};
+use common::r#const::{
+    CASTLE_MAX_LV, COURTYARD_COLS, COURTYARD_ROWS, MAP_COLS, MAP_ROWS, MAX_WAYPOINTS,
+};
 
 pub struct InputHandler;
 
@@ -39,13 +46,20 @@ impl InputHandler {
             return;
         };
 
+        // Text input takes every key
+        let typing = matches!(ui_state.mode, UiMode::KitGrant(_));
         match key.code {
-            KeyCode::Char('q') => {
+            KeyCode::Char('q') if !typing => {
                 shutdown.shutdown(ShutdownReason::Key);
             }
-            KeyCode::Char('y') => ui_state.tab = ModPlayerInfoTab::Castle,
-            KeyCode::Char('x') => ui_state.tab = ModPlayerInfoTab::Logs,
-            KeyCode::Char('c') => ui_state.tab = ModPlayerInfoTab::Debug,
+            KeyCode::Char('y') if !typing => ui_state.tab = ModPlayerInfoTab::Castle,
+            KeyCode::Char('x') if !typing => ui_state.tab = ModPlayerInfoTab::Logs,
+            KeyCode::Char('c') if !typing => ui_state.tab = ModPlayerInfoTab::Debug,
+            KeyCode::Char('e') if !typing => {
+                ui_state.tab = ModPlayerInfoTab::Research;
+                ui_state.mode = UiMode::ResearchSelection(ResearchSelection::new());
+                return;
+            }
             _ => {}
         }
 
@@ -89,7 +103,21 @@ impl InputHandler {
                 _ => {}
             },
             UiMode::Inspect(ref mut inspect) => match (key.code, key.modifiers) {
-                (KeyCode::Esc, _) => ui_state.mode = UiMode::Std,
+                (KeyCode::Esc, _) => {
+                    ui_state.waypoints.clear();
+                    ui_state.mode = UiMode::Std;
+                }
+                // The destination takes the last waypoint
+                (KeyCode::Char('w'), _)
+                    if ui_state.camera.location != CameraLocation::Courtyard
+                        && inspect.redirect.is_none()
+                        && ui_state.waypoints.len() < MAX_WAYPOINTS - 1 =>
+                {
+                    ui_state.waypoints.push(inspect.coord);
+                }
+                (KeyCode::Backspace, _) => {
+                    ui_state.waypoints.pop();
+                }
                 (KeyCode::Char('M'), _) => {
                     Self::change_camera_location(
                         &mut ui_state.camera,
@@ -139,13 +167,35 @@ impl InputHandler {
                                 _ => InteractTarget::GameObj(inspect.selection.unwrap()),
                             };
 
-                            ui_state.mode = UiMode::Interact(interact_target);
+                            if let Some(units_id) = inspect.merge {
+                                if let InteractTarget::GameObj(into_id) = interact_target
+                                    && Self::is_owned_units(into_id, game_state)
+                                {
+                                    let _ = tx.send(T2C::MergeUnits(units_id, into_id));
+                                }
+                                ui_state.mode = UiMode::Std;
+                            } else if let Some(units_id) = inspect.redirect {
+                                Self::handle_units_redirect(
+                                    tx,
+                                    units_id,
+                                    &interact_target,
+                                    &game_state.objs,
+                                );
+                                ui_state.mode = UiMode::Std;
+                            } else {
+                                ui_state.mode = UiMode::Interact(interact_target);
+                            }
                         }
                     }
                     CameraLocation::Courtyard => {
                         if game_state.player.castle_id.is_none() {
                             return;
                         }
+                        if let Some(facility_id) = inspect.relocate {
+                            let _ = tx.send(T2C::RelocateFacility(facility_id, inspect.coord));
+                            ui_state.mode = UiMode::Std;
+                            return;
+                        }
                         let looked_facility =
                             Tui::get_looked_facility(inspect.coord, &game_state.facilities);
                         if let Some(looked_facility) = looked_facility {
@@ -184,8 +234,13 @@ impl InputHandler {
                 _ => {}
             },
             UiMode::Interact(ref mut interact_target) => match (key.code, key.modifiers) {
-                (KeyCode::Esc, _) => ui_state.mode = UiMode::Std,
+                (KeyCode::Esc, _) => {
+                    ui_state.waypoints.clear();
+                    ui_state.mode = UiMode::Std;
+                }
                 (KeyCode::Char('a'), _) => match interact_target {
+                    InteractTarget::GameObj(obj_id)
+                        if Self::is_owned_units(*obj_id, game_state) => {}
                     InteractTarget::MapPos(_) | InteractTarget::GameObj(_) => {
                         ui_state.mode = UiMode::UnitSelection(UnitSelection::from_interact(
                             interact_target.clone(),
@@ -193,6 +248,135 @@ impl InputHandler {
                     }
                     _ => {}
                 },
+                (KeyCode::Char('r'), _) => {
+                    if let InteractTarget::GameObj(obj_id) = interact_target {
+                        if Self::is_owned_units(*obj_id, game_state) {
+                            let _ = tx.send(T2C::RecallUnits(*obj_id));
+                            ui_state.mode = UiMode::Std;
+                        } else if Self::has_lent_units(*obj_id, game_state) {
+                            let _ = tx.send(T2C::RecallReinforcements(*obj_id));
+                            ui_state.mode = UiMode::Std;
+                        }
+                    }
+                }
+                (KeyCode::Char('f'), _) => {
+                    if let InteractTarget::GameObj(obj_id) = interact_target
+                        && Self::is_other_castle(*obj_id, game_state)
+                    {
+                        ui_state.mode =
+                            UiMode::UnitSelection(UnitSelection::for_reinforce(*obj_id));
+                    }
+                }
+                (KeyCode::Char('g'), _) => {
+                    if let InteractTarget::GameObj(obj_id) = interact_target
+                        && Self::is_other_castle(*obj_id, game_state)
+                    {
+                        ui_state.mode = UiMode::ResourceSelection(ResourceSelection::new(*obj_id));
+                    }
+                }
+                (KeyCode::Char('d'), _) => {
+                    if let InteractTarget::GameObj(obj_id) = interact_target
+                        && Self::is_owned_units(*obj_id, game_state)
+                        && let Some(obj) = game_state.objs.get(obj_id)
+                    {
+                        let mut inspect = Inspect::new(obj.get_pos());
+                        inspect.redirect = Some(*obj_id);
+                        ui_state.mode = UiMode::Inspect(inspect);
+                    }
+                }
+                (KeyCode::Char('m'), _) => {
+                    if let InteractTarget::GameObj(obj_id) = interact_target
+                        && Self::is_owned_units(*obj_id, game_state)
+                        && let Some(obj) = game_state.objs.get(obj_id)
+                    {
+                        let mut inspect = Inspect::new(obj.get_pos());
+                        inspect.merge = Some(*obj_id);
+                        ui_state.mode = UiMode::Inspect(inspect);
+                    }
+                }
+                (KeyCode::Char('s'), _) => {
+                    if let InteractTarget::GameObj(obj_id) = interact_target
+                        && Self::is_owned_units(*obj_id, game_state)
+                    {
+                        ui_state.mode = UiMode::UnitSelection(UnitSelection::for_split(*obj_id));
+                    }
+                }
+                (KeyCode::Char('u'), _) => {
+                    if let InteractTarget::GameObj(obj_id) = interact_target
+                        && game_state.player.castle_id == Some(*obj_id)
+                        && game_state
+                            .castle
+                            .as_ref()
+                            .is_some_and(|castle| castle.lv < CASTLE_MAX_LV)
+                    {
+                        let _ = tx.send(T2C::UpgradeCastle);
+                        ui_state.mode = UiMode::Std;
+                    } else if let InteractTarget::Facility(facility_id) = interact_target
+                        && let Some(facility) = game_state.get_facility(*facility_id)
+                        && facility.is_built()
+                        && !facility.is_max_lv()
+                        && game_state.get_build_job(*facility_id).is_none()
+                    {
+                        let _ = tx.send(T2C::UpgradeFacility(*facility_id));
+                        ui_state.mode = UiMode::Std;
+                    }
+                }
+                (KeyCode::Char('K'), _) => {
+                    if let InteractTarget::GameObj(obj_id) = interact_target
+                        && let Some(GameObjE::Castle(_)) = game_state.objs.get(obj_id)
+                    {
+                        ui_state.mode = UiMode::KitGrant(KitGrant::new(*obj_id));
+                    }
+                }
+                (KeyCode::Char('t'), _) => {
+                    if let InteractTarget::Facility(facility_id) = interact_target
+                        && let Some(facility) = game_state.get_facility(*facility_id)
+                        && !facility.r#type.trainable_units().is_empty()
+                    {
+                        ui_state.mode = UiMode::TrainingSelection(TrainingSelection::new(
+                            *facility_id,
+                            facility.r#type,
+                        ));
+                    }
+                }
+                (KeyCode::Backspace, _) => {
+                    if let InteractTarget::Facility(facility_id) = interact_target
+                        && let Some(job) = game_state.get_training_jobs(*facility_id).last()
+                    {
+                        let _ = tx.send(T2C::CancelTraining(job.id));
+                    }
+                }
+                (KeyCode::Char('+'), _) => {
+                    if let InteractTarget::Facility(facility_id) = interact_target
+                        && let Some(facility) = game_state.get_facility(*facility_id)
+                        && facility.workers < facility.max_workers()
+                    {
+                        let _ = tx.send(T2C::AssignWorkers(*facility_id, facility.workers + 1));
+                    }
+                }
+                (KeyCode::Char('-'), _) => {
+                    if let InteractTarget::Facility(facility_id) = interact_target
+                        && let Some(facility) = game_state.get_facility(*facility_id)
+                        && facility.workers > 0
+                    {
+                        let _ = tx.send(T2C::AssignWorkers(*facility_id, facility.workers - 1));
+                    }
+                }
+                (KeyCode::Char('b'), _) => {
+                    if let InteractTarget::Facility(facility_id) = interact_target {
+                        let _ = tx.send(T2C::DemolishFacility(*facility_id));
+                        ui_state.mode = UiMode::Std;
+                    }
+                }
+                (KeyCode::Char('v'), _) => {
+                    if let InteractTarget::Facility(facility_id) = interact_target
+                        && let Some(facility) = game_state.get_facility(*facility_id)
+                    {
+                        let mut inspect = Inspect::new(facility.pos);
+                        inspect.relocate = Some(*facility_id);
+                        ui_state.mode = UiMode::Inspect(inspect);
+                    }
+                }
                 (KeyCode::Char('n'), _) => {
                     if let InteractTarget::CourtyardPos(pos) = interact_target {
                         ui_state.mode = UiMode::FacilitySelection(FacilitySelection::new(*pos));
@@ -201,16 +385,24 @@ impl InputHandler {
                 _ => {}
             },
             UiMode::UnitSelection(ref mut selection) => {
-                let Some(ref castle) = game_state.castle else {
+                let Some(available) = selection.get_available(game_state) else {
                     return;
                 };
 
                 match (key.code, key.modifiers) {
-                    (KeyCode::Esc, _) => ui_state.mode = UiMode::Std,
+                    (KeyCode::Esc, _) => {
+                        ui_state.waypoints.clear();
+                        ui_state.mode = UiMode::Std;
+                    }
                     (KeyCode::Char('a'), _) => {
-                        Self::handle_unit_deploy(tx, selection);
+                        Self::handle_unit_deploy(tx, selection, &mut ui_state.waypoints);
                         ui_state.mode = UiMode::Std;
                     }
+                    (KeyCode::Char('p'), _) => {
+                        if let InteractTarget::MapPos(_) = selection.interact_target {
+                            selection.patrol = !selection.patrol;
+                        }
+                    }
                     (KeyCode::Enter, _) => {
                         if let Some(ref string) = selection.active_input.1 {
                             let unit_index = selection.active_input.0.as_index();
@@ -218,7 +410,7 @@ impl InputHandler {
                             selection.selected_units.quantities[unit_index] = string
                                 .parse()
                                 .unwrap_or(0)
-                                .min(castle.units.quantities[unit_index]);
+                                .min(available.quantities[unit_index]);
                             selection.active_input.1 = None;
                         } else {
                             selection.active_input.1 = Some(String::new());
@@ -245,6 +437,115 @@ impl InputHandler {
                     _ => {}
                 }
             }
+            UiMode::ResourceSelection(ref mut selection) => {
+                let Some(ref castle) = game_state.castle else {
+                    return;
+                };
+                match (key.code, key.modifiers) {
+                    (KeyCode::Esc, _) => ui_state.mode = UiMode::Std,
+                    (KeyCode::Char('g'), _) => {
+                        let _ = tx.send(T2C::GiftResources(
+                            selection.target,
+                            selection.selected.clone(),
+                        ));
+                        ui_state.mode = UiMode::Std;
+                    }
+                    (KeyCode::Enter, _) => {
+                        let resource = selection.active_input.0;
+                        if let Some(ref string) = selection.active_input.1 {
+                            *selection.selected.get_mut(resource) = string
+                                .parse()
+                                .unwrap_or(0)
+                                .min(castle.resources.get(resource));
+                            selection.active_input.1 = None;
+                        } else {
+                            selection.active_input.1 = Some(String::new());
+                        }
+                    }
+                    (KeyCode::Char(c), _) if c.is_ascii_digit() => {
+                        if let Some(ref mut string) = selection.active_input.1 {
+                            string.push(c);
+                        }
+                    }
+                    (KeyCode::Backspace, _) => {
+                        if let Some(ref mut string) = selection.active_input.1 {
+                            let _ = string.pop();
+                        }
+                    }
+                    (KeyCode::Up, _) if selection.active_input.1.is_none() => {
+                        let index = selection.active_input.0.as_index().saturating_sub(1);
+                        selection.active_input.0 = ResourceType::from_index(index);
+                    }
+                    (KeyCode::Down, _) if selection.active_input.1.is_none() => {
+                        let index =
+                            (selection.active_input.0.as_index() + 1).min(ResourceType::COUNT - 1);
+                        selection.active_input.0 = ResourceType::from_index(index);
+                    }
+                    _ => {}
+                }
+            }
+            UiMode::KitGrant(ref mut grant) => match key.code {
+                KeyCode::Esc => ui_state.mode = UiMode::Std,
+                KeyCode::Enter => {
+                    let _ = tx.send(T2C::GrantKit(grant.target, grant.kit.clone()));
+                    ui_state.mode = UiMode::Std;
+                }
+                KeyCode::Backspace => {
+                    let _ = grant.kit.pop();
+                }
+                KeyCode::Char(c) if c.is_ascii_alphanumeric() || c == '_' => grant.kit.push(c),
+                _ => {}
+            },
+            UiMode::TrainingSelection(ref mut selection) => match (key.code, key.modifiers) {
+                (KeyCode::Esc, _) => ui_state.mode = UiMode::Std,
+                (KeyCode::Enter, _) => {
+                    if let Some(ref string) = selection.active_input.1 {
+                        let count = string.parse().unwrap_or(0);
+                        if let Some(unit) = selection.units.get(selection.active_input.0)
+                            && count > 0
+                        {
+                            let _ = tx.send(T2C::TrainUnits(*unit, count));
+                        }
+                        ui_state.mode =
+                            UiMode::Interact(InteractTarget::Facility(selection.facility_id));
+                    } else {
+                        selection.active_input.1 = Some(String::new());
+                    }
+                }
+                (KeyCode::Char(c), _) if c.is_ascii_digit() => {
+                    if let Some(ref mut string) = selection.active_input.1 {
+                        string.push(c);
+                    }
+                }
+                (KeyCode::Backspace, _) => {
+                    if let Some(ref mut string) = selection.active_input.1 {
+                        let _ = string.pop();
+                    }
+                }
+                (KeyCode::Up, _) if selection.active_input.1.is_none() => {
+                    selection.active_input.0 = selection.active_input.0.saturating_sub(1);
+                }
+                (KeyCode::Down, _) if selection.active_input.1.is_none() => {
+                    selection.active_input.0 =
+                        (selection.active_input.0 + 1).min(selection.units.len().saturating_sub(1));
+                }
+                _ => {}
+            },
+            UiMode::ResearchSelection(ref mut selection) => match (key.code, key.modifiers) {
+                (KeyCode::Esc, _) => ui_state.mode = UiMode::Std,
+                (KeyCode::Enter, _) => {
+                    let _ = tx.send(T2C::Research(selection.active));
+                }
+                (KeyCode::Up, KeyModifiers::NONE) => {
+                    let index = selection.active.as_index().saturating_sub(1);
+                    selection.active = TechType::from_index(index);
+                }
+                (KeyCode::Down, KeyModifiers::NONE) => {
+                    let index = (selection.active.as_index() + 1).min(TechType::COUNT - 1);
+                    selection.active = TechType::from_index(index);
+                }
+                _ => {}
+            },
             UiMode::FacilitySelection(ref mut selection) => {
                 let Some(_) = game_state.castle else {
                     return;
@@ -253,10 +554,7 @@ impl InputHandler {
                     (KeyCode::Esc, _) => ui_state.mode = UiMode::Std,
                     (KeyCode::Enter, _) => {
                         let _ = tx.send(T2C::NewFacility((selection.pos, selection.active)));
-                        ui_state.mode = UiMode::Inspect(Inspect {
-                            coord: selection.pos,
-                            selection: None,
-                        });
+                        ui_state.mode = UiMode::Inspect(Inspect::new(selection.pos));
                     }
                     (KeyCode::Up, KeyModifiers::NONE) => {
                         Self::move_facility_selection(-1, selection)
@@ -314,27 +612,89 @@ impl InputHandler {
                 CameraLocation::Map => ui_state.camera.map,
                 CameraLocation::Courtyard => ui_state.camera.courtyard,
             };
-            ui_state.mode = UiMode::Inspect(Inspect {
-                coord,
-                selection: None,
-            });
+            ui_state.mode = UiMode::Inspect(Inspect::new(coord));
         } else if let UiMode::Inspect(_) = ui_state.mode {
             ui_state.mode = UiMode::Std;
         }
     }
 
-    fn handle_unit_deploy(tx: &tokio::sync::mpsc::UnboundedSender<T2C>, selection: &UnitSelection) {
+    fn handle_unit_deploy(
+        tx: &tokio::sync::mpsc::UnboundedSender<T2C>,
+        selection: &UnitSelection,
+        waypoints: &mut Vec<GameCoord>,
+    ) {
+        let mut waypoints = std::mem::take(waypoints);
+        if let Some(units_id) = selection.source {
+            let _ = tx.send(T2C::SplitUnits(units_id, selection.selected_units.clone()));
+            return;
+        }
+        if selection.reinforce
+            && let InteractTarget::GameObj(castle_id) = selection.interact_target
+        {
+            let _ = tx.send(T2C::ReinforceCastle(
+                castle_id,
+                selection.selected_units.clone(),
+            ));
+            return;
+        }
         match selection.interact_target {
             InteractTarget::GameObj(obj_id) => {
                 let _ = tx.send(T2C::AttackCastle(obj_id, selection.selected_units.clone()));
             }
             InteractTarget::MapPos(pos) => {
-                let _ = tx.send(T2C::SendUnits(pos, selection.selected_units.clone()));
+                waypoints.push(pos);
+                let _ = tx.send(T2C::SendUnits(
+                    waypoints,
+                    selection.selected_units.clone(),
+                    selection.patrol,
+                ));
             }
             _ => {}
         };
     }
 
+    fn handle_units_redirect(
+        tx: &UnboundedSender<T2C>,
+        units_id: GameId,
+        interact_target: &InteractTarget,
+        objs: &HashMap<GameId, GameObjE>,
+    ) {
+        match interact_target {
+            InteractTarget::GameObj(obj_id) => match objs.get(obj_id) {
+                Some(GameObjE::Castle(_)) => {
+                    let _ = tx.send(T2C::RedirectAttack(units_id, *obj_id));
+                }
+                Some(obj) => {
+                    let _ = tx.send(T2C::RedirectUnits(units_id, obj.get_pos()));
+                }
+                None => {}
+            },
+            InteractTarget::MapPos(pos) => {
+                let _ = tx.send(T2C::RedirectUnits(units_id, *pos));
+            }
+            _ => {}
+        }
+    }
+
+    fn is_owned_units(obj_id: GameId, game_state: &GameState) -> bool {
+        matches!(
+            game_state.objs.get(&obj_id),
+            Some(GameObjE::DeployedUnits(units)) if Some(units.owner_id) == game_state.player.castle_id
+        )
+    }
+
+    fn is_other_castle(obj_id: GameId, game_state: &GameState) -> bool {
+        matches!(game_state.objs.get(&obj_id), Some(GameObjE::Castle(_)))
+            && game_state.player.castle_id.is_some_and(|id| id != obj_id)
+    }
+
+    fn has_lent_units(castle_id: GameId, game_state: &GameState) -> bool {
+        game_state
+            .castle
+            .as_ref()
+            .is_some_and(|castle| castle.lent_units.contains_key(&castle_id))
+    }
+
     fn move_inspect(
         mut dx: isize,
         mut dy: isize,
@@ -1,7 +1,9 @@
 use std::collections::VecDeque;
 
+use common::packets::Severity;
+
 pub struct Logs {
-    pub content: VecDeque<String>,
+    pub content: VecDeque<(String, Severity)>,
     max_len: usize,
 }
 
@@ -13,10 +15,10 @@ impl Logs {
         }
     }
 
-    pub fn add(&mut self, item: String) {
+    pub fn add(&mut self, item: String, severity: Severity) {
         if self.content.len() >= self.max_len {
             let _ = self.content.pop_front();
         }
-        self.content.push_back(item);
+        self.content.push_back((item, severity));
     }
 }
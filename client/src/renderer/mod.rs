@@ -10,4 +10,5 @@ pub enum ModPlayerInfoTab {
     Castle,
     Debug,
     Logs,
+    Research,
 }
@@ -1,7 +1,7 @@
 use std::collections::HashMap;
 
 use common::r#const::{COURTYARD_COLS, COURTYARD_ROWS, MAP_COLS, MAP_ROWS};
-use common::courtyard::Facility;
+use common::courtyard::{BuildJob, Facility, courtyard_area};
 use common::game_objs::GameObjE;
 use common::map::Tile;
 use common::{GameCoord, GameId};
@@ -12,7 +12,7 @@ use crate::ansi::{BLACK, WHITE};
 use crate::assets::*;
 use crate::camera::{Camera, CameraLocation};
 use crate::coord::TermCoord;
-use crate::game_state::GameState;
+use crate::game_state::{GameState, RequestOrigin};
 use crate::renderer::module::Module;
 use crate::ui_state::{UiMode, UiState};
 
@@ -36,7 +36,7 @@ impl ModCentral {
     pub fn render(&mut self, game_state: &mut GameState, ui_state: &UiState) -> Vec<Vec<TermCell>> {
         let camera_coord = ui_state.camera.get_pos();
 
-        let title = match ui_state.camera.location {
+        let mut title = match ui_state.camera.location {
             CameraLocation::Map => {
                 let title = format!("Castli | map {}", camera_coord);
                 self.draw_map(&game_state.map, game_state.time.night, &ui_state.camera);
@@ -60,9 +60,11 @@ impl ModCentral {
             }
             CameraLocation::Courtyard => {
                 let title = format!("Castli | courtyard {}", camera_coord);
-                self.draw_courtyard(&ui_state.camera);
+                let castle_lv = game_state.castle.as_ref().map_or(1, |castle| castle.lv);
+                self.draw_courtyard(&ui_state.camera, castle_lv);
                 self.draw_facilities(
                     &game_state.facilities,
+                    &game_state.build_queue,
                     &ui_state.camera,
                     game_state.time.night,
                 );
@@ -70,6 +72,15 @@ impl ModCentral {
             }
         };
 
+        // Adding the waypoints
+        if ui_state.camera.location != CameraLocation::Courtyard {
+            for waypoint in ui_state.waypoints.iter() {
+                if let Some(term_coord) = TermCoord::from_game_coord(*waypoint, &ui_state.camera) {
+                    self.module.draw_asset(WAYPOINT, term_coord);
+                }
+            }
+        }
+
         // Adding the cursor
         if let UiMode::Inspect(ref inspect) = ui_state.mode
             && let Some(term_coord) = TermCoord::from_game_coord(inspect.coord, &ui_state.camera)
@@ -77,6 +88,15 @@ impl ModCentral {
             self.module.draw_asset(&CURSOR, term_coord);
         }
 
+        // Status line of the view, with the last command it had rejected
+        let origin = match ui_state.camera.location {
+            CameraLocation::Courtyard => RequestOrigin::Courtyard,
+            CameraLocation::Map | CameraLocation::WorldMap => RequestOrigin::Map,
+        };
+        if let Some(rejection) = game_state.get_rejection(origin) {
+            title = format!("{} | {}", title, rejection);
+        }
+
         self.module.set_name(title);
         self.module.center();
         self.module.get_cells()
@@ -151,9 +171,10 @@ impl ModCentral {
         }
     }
 
-    fn draw_courtyard(&mut self, camera: &Camera) {
+    fn draw_courtyard(&mut self, camera: &Camera, castle_lv: u32) {
         let drawable_size = self.module.drawable_size();
         let camera_pos = camera.courtyard;
+        let (corner, area) = courtyard_area(castle_lv);
 
         for tile_row in camera_pos.y..camera_pos.y + drawable_size.y * 2 {
             if tile_row & 1 == 1 {
@@ -167,7 +188,14 @@ impl ModCentral {
                 };
 
                 if tile_row < COURTYARD_ROWS && tile_col < COURTYARD_COLS {
-                    self.module.draw_cell(COURTYARD_BK_CELL, term_pos);
+                    let unlocked = (corner.y..corner.y + area.y).contains(&tile_row)
+                        && (corner.x..corner.x + area.x).contains(&tile_col);
+                    let cell = if unlocked {
+                        COURTYARD_BK_CELL
+                    } else {
+                        COURTYARD_LOCKED_CELL
+                    };
+                    self.module.draw_cell(cell, term_pos);
                 }
             }
         }
@@ -232,17 +260,49 @@ impl ModCentral {
     fn draw_facilities(
         &mut self,
         facilities: &HashMap<u8, Facility>,
+        build_queue: &[BuildJob],
         camera: &Camera,
         night: bool,
     ) {
-        for (_, facility) in facilities.iter() {
-            let asset = FacilityAsset::get_asset(facility, night);
+        for (id, facility) in facilities.iter() {
             let pos = facility.pos;
             let Some(term_coord) = TermCoord::from_game_coord(pos, camera) else {
                 continue;
             };
+            let size = facility.r#type.size();
+            let (rows, cols) = (size.y / 2, size.x);
+
+            if facility.is_built() {
+                let asset = FacilityAsset::get_asset(facility, night);
+                self.module.draw_asset(asset, term_coord);
+            } else {
+                let scaffolding = FacilityAsset::get_scaffolding(night);
+                for row in 0..rows {
+                    for col in 0..cols {
+                        let cell = scaffolding[row % scaffolding.len()][col % scaffolding[0].len()];
+                        self.module.draw_cell(
+                            cell,
+                            TermCoord::new(term_coord.y + row, term_coord.x + col),
+                        );
+                    }
+                }
+            }
 
-            self.module.draw_asset(asset, term_coord);
+            // Progress bar on the last row of the facility
+            if let Some(job) = build_queue.iter().find(|job| job.facility_id == *id) {
+                let done = cols * job.progress_percent() as usize / 100;
+                for col in 0..cols {
+                    let cell = if col < done {
+                        PROGRESS_DONE_TERMCELL
+                    } else {
+                        PROGRESS_LEFT_TERMCELL
+                    };
+                    self.module.draw_cell(
+                        cell,
+                        TermCoord::new(term_coord.y + rows - 1, term_coord.x + col),
+                    );
+                }
+            }
         }
     }
 }
@@ -48,7 +48,21 @@ impl ModInspect {
                     };
                 }
             }
-            self.module.set_name(format!("inspect | {}", inspect.coord));
+            let title = if inspect.redirect.is_some() {
+                "redirect"
+            } else if inspect.merge.is_some() {
+                "merge"
+            } else if inspect.relocate.is_some() {
+                "relocate"
+            } else {
+                "inspect"
+            };
+            if !ui_state.waypoints.is_empty() {
+                self.module
+                    .push_row_with_text(&format!("waypoints: {}", ui_state.waypoints.len()));
+            }
+            self.module
+                .set_name(format!("{} | {}", title, inspect.coord));
             Some(self.module.get_cells().clone())
         } else {
             None
@@ -110,9 +124,13 @@ impl ModInspect {
                     };
                 }
                 GameObjE::DeployedUnits(units) => {
-                    let owned = *owned_castle == Some(*id);
+                    let owned = *owned_castle == Some(units.owner_id);
                     let icon = GameObjAsset::get_asset(obj, owned)[0][0];
-                    let name_string = "{Units}".to_string();
+                    let name_string = if units.embarked {
+                        "{Fleet}".to_string()
+                    } else {
+                        "{Units}".to_string()
+                    };
 
                     self.module.push_row_with_text(&name_string);
                     self.module.draw_cell_last_row(icon, 0);
@@ -124,6 +142,25 @@ impl ModInspect {
                     let info_string = format!("  owner {}, id {}", units.owner_id, id).to_string();
 
                     self.module.push_row_with_text(&info_string);
+                    self.module
+                        .push_row_with_text(&format!("  {:?}", units.status));
+                    if let Some(ref unit_group) = units.units {
+                        for (unit, count) in unit_group.iter_present() {
+                            self.module
+                                .push_row_with_text(&format!("  {:?}: {}", unit, count));
+                        }
+                    }
+                    if let Some(ref loot) = units.loot
+                        && !loot.is_empty()
+                    {
+                        self.module.push_row_with_text(&format!("  loot: {}", loot));
+                    }
+                    if let Some(ref waypoints) = units.waypoints {
+                        for waypoint in waypoints.iter() {
+                            self.module
+                                .push_row_with_text(&format!("  -> {}", waypoint));
+                        }
+                    }
                 }
             }
         }
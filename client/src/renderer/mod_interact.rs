@@ -1,4 +1,10 @@
-use common::{all_facilities, all_units, game_objs::GameObjE};
+use common::{
+    all_facilities, all_resources, all_units,
+    r#const::CASTLE_MAX_LV,
+    courtyard::{BuildKind, castle_upgrade_cost},
+    game_objs::GameObjE,
+    research,
+};
 
 use crate::{
     assets::{SELECTION_TERMCELL, TermCell},
@@ -30,10 +36,48 @@ impl ModInteract {
                         match obj {
                             Some(GameObjE::Castle(castle)) => {
                                 self.module.push_row_with_text(&castle.name);
-                                self.module.push_row_with_text("a: attack");
+                                self.module.push_row_with_text(&format!(
+                                    "lv {}/{}",
+                                    castle.lv, CASTLE_MAX_LV
+                                ));
+                                if game_state.player.castle_id == Some(*obj_id)
+                                    && castle.lv < CASTLE_MAX_LV
+                                {
+                                    self.module.push_row_with_text("u: upgrade castle");
+                                    self.module.push_row_with_text(
+                                        &castle_upgrade_cost(castle.lv).to_string(),
+                                    );
+                                }
+                                if game_state.player.castle_id != Some(*obj_id) {
+                                    self.module.push_row_with_text("a: attack");
+                                    self.module.push_row_with_text("f: reinforce");
+                                    self.module.push_row_with_text("g: gift resources");
+                                }
+                                if let Some(lent) = game_state
+                                    .castle
+                                    .as_ref()
+                                    .and_then(|owned| owned.lent_units.get(obj_id))
+                                {
+                                    self.module.push_row_with_text(&format!(
+                                        "r: recall reinforcements ({})",
+                                        lent.quantities.iter().sum::<u32>()
+                                    ));
+                                }
                             }
                             Some(GameObjE::Structure(_)) => {}
-                            Some(GameObjE::DeployedUnits(_)) => {}
+                            Some(GameObjE::DeployedUnits(units)) => {
+                                if Some(units.owner_id) == game_state.player.castle_id {
+                                    self.module.push_row_with_text("Your units");
+                                    self.module
+                                        .push_row_with_text(&format!("{:?}", units.status));
+                                    self.module.push_row_with_text("r: recall");
+                                    self.module.push_row_with_text("d: redirect");
+                                    self.module.push_row_with_text("m: merge into other units");
+                                    self.module.push_row_with_text("s: split");
+                                } else {
+                                    self.module.push_row_with_text("Enemy units");
+                                }
+                            }
                             None => {
                                 self.module
                                     .push_row_with_text("The object doesn't exist anymore");
@@ -51,8 +95,59 @@ impl ModInteract {
                         };
                         self.module
                             .push_row_with_text(&format!("{:?}", facility.r#type));
-                        self.module
-                            .push_row_with_text(&format!("lv {}", facility.lv));
+                        self.module.push_row_with_text(&format!(
+                            "lv {}/{}",
+                            facility.lv,
+                            facility.r#type.max_lv()
+                        ));
+                        if facility.r#type.workers_per_lv() > 0 {
+                            self.module.push_row_with_text(&format!(
+                                "workers {}/{} (idle peasants: {})",
+                                facility.workers,
+                                facility.max_workers(),
+                                game_state.peasants
+                            ));
+                            self.module
+                                .push_row_with_text("+/-: assign/remove a worker");
+                        }
+                        if !facility.r#type.trainable_units().is_empty() {
+                            self.module.push_row_with_text("t: train units");
+                            let jobs = game_state.get_training_jobs(*facility_id);
+                            for job in jobs.iter() {
+                                self.module.push_row_with_text(&format!(
+                                    "{:?} {}/{}",
+                                    job.unit, job.trained, job.count
+                                ));
+                            }
+                            if !jobs.is_empty() {
+                                self.module
+                                    .push_row_with_text("backspace: cancel last order");
+                            }
+                        }
+                        self.module.push_row_with_text("v: relocate");
+                        self.module.push_row_with_text("b: demolish");
+                        if let Some((queue_pos, job)) = game_state.get_build_job(*facility_id) {
+                            let action = match job.kind {
+                                BuildKind::Construction => "building",
+                                BuildKind::Upgrade => "upgrading",
+                            };
+                            self.module.push_row_with_text(&format!(
+                                "{} {}% (queue #{})",
+                                action,
+                                job.progress_percent(),
+                                queue_pos + 1
+                            ));
+                        } else if !facility.is_max_lv() {
+                            let cost =
+                                facility
+                                    .upgrade_cost()
+                                    .percent(research::facility_cost_percent(
+                                        game_state.get_researched(),
+                                        facility.r#type,
+                                    ));
+                            self.module.push_row_with_text("u: upgrade");
+                            self.module.push_row_with_text(&cost.to_string());
+                        }
                     }
                     InteractTarget::CourtyardPos(_) => {
                         self.module.push_row_with_text("n: build");
@@ -63,14 +158,12 @@ impl ModInteract {
                 Some(self.module.get_cells().clone())
             }
             UiMode::UnitSelection(ref selection) => {
-                let Some(ref castle) = game_state.castle else {
-                    return None;
-                };
+                let available = selection.get_available(game_state)?;
                 let all_units = all_units!();
 
                 for (i, unit) in all_units.iter().enumerate() {
                     let is_active = selection.active_input.0 == *unit;
-                    let total = castle.units.quantities[i];
+                    let total = available.quantities[i];
 
                     let display_quantities =
                         if is_active && let Some(ref input_str) = selection.active_input.1 {
@@ -93,15 +186,101 @@ impl ModInteract {
                 self.module.push_empty_row();
                 self.module.push_row_with_text("enter: select/set amount");
                 self.module.push_row_with_text("a: confirm");
+                if let InteractTarget::MapPos(_) = selection.interact_target {
+                    self.module.push_row_with_text(&format!(
+                        "p: patrol [{}]",
+                        if selection.patrol { "x" } else { " " }
+                    ));
+                    self.module
+                        .push_row_with_text(&format!("waypoints: {}", ui_state.waypoints.len()));
+                }
+
+                let name = if selection.source.is_some() {
+                    "split units"
+                } else if selection.reinforce {
+                    "reinforce"
+                } else {
+                    "unit selection"
+                };
+                self.module.set_name(name.to_string());
+                Some(self.module.get_cells().clone())
+            }
+            UiMode::ResourceSelection(ref selection) => {
+                let castle = game_state.castle.as_ref()?;
+
+                for resource in all_resources!() {
+                    let is_active = selection.active_input.0 == resource;
+                    let total = castle.resources.get(resource);
+
+                    let display_quantities =
+                        if is_active && let Some(ref input_str) = selection.active_input.1 {
+                            format!("{}_/{}", input_str, total)
+                        } else {
+                            format!("{}/{}", selection.selected.get(resource), total)
+                        };
+
+                    self.module
+                        .push_row_with_text(&format!("{:?}: {}", resource, display_quantities));
+                    if is_active {
+                        self.module.draw_cell_last_row(
+                            SELECTION_TERMCELL,
+                            self.module.drawable_size().x - 1,
+                        );
+                    }
+                }
+                self.module.push_empty_row();
+                self.module.push_row_with_text("enter: select/set amount");
+                self.module.push_row_with_text("g: confirm");
+
+                self.module.set_name("gift resources".to_string());
+                Some(self.module.get_cells().clone())
+            }
+            UiMode::KitGrant(ref grant) => {
+                self.module
+                    .push_row_with_text(&format!("kit: {}_", grant.kit));
+                self.module.push_empty_row();
+                self.module.push_row_with_text("enter: grant");
+
+                self.module.set_name("admin | grant kit".to_string());
+                Some(self.module.get_cells().clone())
+            }
+            UiMode::TrainingSelection(ref selection) => {
+                self.module
+                    .push_row_with_text(&format!("Peasants: {}", game_state.peasants));
+                self.module.push_empty_row();
+
+                for (i, unit) in selection.units.iter().enumerate() {
+                    let is_active = selection.active_input.0 == i;
+                    let amount = match selection.active_input.1 {
+                        Some(ref input_str) if is_active => format!("{}_", input_str),
+                        _ => String::new(),
+                    };
+                    let cost = unit.training_cost();
+
+                    self.module
+                        .push_row_with_text(&format!("{:?}: {}", unit, amount));
+                    if is_active {
+                        self.module.draw_cell_last_row(
+                            SELECTION_TERMCELL,
+                            self.module.drawable_size().x - 1,
+                        );
+                    }
+                    self.module.push_row_with_text(&cost.to_string());
+                    self.module
+                        .push_row_with_text(&format!("Peasants: {}", unit.training_peasants()));
+                    self.module.push_empty_row();
+                }
+                self.module.push_row_with_text("enter: set amount/train");
 
-                self.module.set_name("unit selection".to_string());
+                self.module.set_name("train units".to_string());
                 Some(self.module.get_cells().clone())
             }
             UiMode::FacilitySelection(ref selection) => {
                 let all_facilities = all_facilities!();
 
+                let castle_lv = game_state.castle.as_ref().map_or(1, |castle| castle.lv);
                 for facility_type in all_facilities.iter() {
-                    let total = facility_type.max_count();
+                    let total = facility_type.max_count(castle_lv);
                     let owned = game_state
                         .facilities
                         .iter()
@@ -110,13 +289,23 @@ impl ModInteract {
 
                     let is_active = selection.active == *facility_type;
 
-                    let display_quantities = format!("{}/{}", owned, total);
+                    let missing_tech = research::required_tech(*facility_type)
+                        .filter(|tech| !game_state.get_researched().contains(tech));
+                    let display_quantities = if castle_lv < facility_type.unlock_lv() {
+                        format!("castle lv {} needed", facility_type.unlock_lv())
+                    } else if let Some(tech) = missing_tech {
+                        format!("{:?} needed", tech)
+                    } else {
+                        format!("{}/{}", owned, total)
+                    };
                     let quantities_text = format!("{:?}: {}", facility_type, display_quantities);
-                    let price_text = format!(
-                        "Wood: {}, Stone: {}",
-                        facility_type.base_cost().wood,
-                        facility_type.base_cost().stone
-                    );
+                    let price_text = facility_type
+                        .base_cost()
+                        .percent(research::facility_cost_percent(
+                            game_state.get_researched(),
+                            *facility_type,
+                        ))
+                        .to_string();
 
                     self.module.push_row_with_text(&quantities_text);
                     if is_active {
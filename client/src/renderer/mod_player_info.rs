@@ -1,14 +1,21 @@
 use crate::ansi::*;
 use crate::assets::*;
 use crate::coord::TermCoord;
-use crate::game_state::GameState;
+use crate::game_state::{GameState, RequestOrigin};
 use crate::logs::Logs;
 use crate::renderer::ModPlayerInfoTab;
 use crate::renderer::module::Module;
-use crate::ui_state::UiState;
+use crate::ui_state::{UiMode, UiState};
 use common::Time;
+use common::all_resources;
+use common::all_techs;
 use common::all_units;
+use common::game_objs::GameObjE;
+use common::packets::Severity;
 use common::player::PlayerE;
+use common::research::TechType;
+use common::resources::ResourceType;
+use crossterm::style::Color;
 
 pub struct ModPlayerInfo {
     module: Module,
@@ -31,8 +38,9 @@ impl ModPlayerInfo {
                 self.draw_debug_tab(frame_dt, &game_state.player, &game_state.time)
             }
             ModPlayerInfoTab::Logs => self.draw_logs_tab(&game_state.logs),
+            ModPlayerInfoTab::Research => self.draw_research_tab(game_state, ui_state),
         };
-        let title = "(y): me | (x): logs | (c): debug".to_string();
+        let title = "(y): me | (x): logs | (c): debug | (e): research".to_string();
         self.module.set_name(title);
         self.module.get_cells().clone()
     }
@@ -58,11 +66,26 @@ impl ModPlayerInfo {
             return;
         };
 
-        let alive_str = if castle.alive { "Alive :)" } else { "Dead x|" };
+        let alive_str = if castle.alive {
+            format!("Alive :) lv {}", castle.lv)
+        } else {
+            "Dead x|".to_string()
+        };
         let pos_str = format!("{}", castle.pos);
         let time_str = format!("Time: {}", game_state.time.h);
-        let wood_str = format!("Wood: {}", castle.resources.wood);
-        let stone_str = format!("Stone: {}", castle.resources.stone);
+        let mut resource_strings = Vec::new();
+        for resource in all_resources!() {
+            let amount = castle.resources.get(resource);
+            let cap = castle.storage_cap.get(resource);
+            let s = match resource {
+                ResourceType::Food => format!(
+                    "{:?}: {}/{} ({:+}/tick)",
+                    resource, amount, cap, castle.food_balance
+                ),
+                _ => format!("{:?}: {}/{}", resource, amount, cap),
+            };
+            resource_strings.push(s);
+        }
 
         let mut unit_strings = Vec::new();
         for unit_type in all_units!() {
@@ -70,18 +93,36 @@ impl ModPlayerInfo {
             let s = format!("{:?}: {}", unit_type, count);
             unit_strings.push(s);
         }
+        unit_strings.push(format!("Upkeep: {}/tick", castle.upkeep));
 
         let mut infos_to_print = vec![
             (castle.name.as_str(), 0),
-            (alive_str, 1),
+            (alive_str.as_str(), 1),
             (pos_str.as_str(), 2),
             (time_str.as_str(), 3),
-            (wood_str.as_str(), 5),
-            (stone_str.as_str(), 6),
         ];
 
+        for (i, resource_str) in resource_strings.iter().enumerate() {
+            infos_to_print.push((resource_str.as_str(), 5 + i));
+        }
+
+        let units_row = 6 + resource_strings.len();
         for (i, unit_str) in unit_strings.iter().enumerate() {
-            infos_to_print.push((unit_str.as_str(), 8 + i));
+            infos_to_print.push((unit_str.as_str(), units_row + i));
+        }
+
+        let mut lent_strings = Vec::new();
+        for (castle_id, units) in castle.lent_units.iter() {
+            let name = match game_state.objs.get(castle_id) {
+                Some(GameObjE::Castle(host)) => host.name.as_str(),
+                _ => "?",
+            };
+            let count: u32 = units.quantities.iter().sum();
+            lent_strings.push(format!("In {}: {} units", name, count));
+        }
+        let lent_row = units_row + 1 + unit_strings.len();
+        for (i, lent_str) in lent_strings.iter().enumerate() {
+            infos_to_print.push((lent_str.as_str(), lent_row + i));
         }
 
         for (text, row) in infos_to_print {
@@ -89,15 +130,101 @@ impl ModPlayerInfo {
         }
     }
 
+    // One row per tech, then the details of the one selected
+    fn draw_research_tab(&mut self, game_state: &GameState, ui_state: &UiState) {
+        let Some(ref castle) = game_state.castle else {
+            self.module.draw_text_in_row("You have no castle", 0);
+            return;
+        };
+        let active = match ui_state.mode {
+            UiMode::ResearchSelection(ref selection) => Some(selection.active),
+            _ => None,
+        };
+
+        let current = match castle.research_queue.first() {
+            Some(job) => format!(
+                "Researching {:?} {}% ({} queued)",
+                job.tech,
+                job.progress_percent(),
+                castle.research_queue.len()
+            ),
+            None => "Nothing being researched".to_string(),
+        };
+        self.module.draw_text_in_row(&current, 0);
+        if let Some(rejection) = game_state.get_rejection(RequestOrigin::Research) {
+            self.module.draw_text_in_row(rejection, 1);
+        }
+
+        let selection_col = self.module.drawable_size().x - 1;
+        for (i, tech) in all_techs!().iter().enumerate() {
+            let status = if castle.researched.contains(tech) {
+                "done"
+            } else if castle.research_queue.iter().any(|job| job.tech == *tech) {
+                "queued"
+            } else if tech
+                .prerequisites()
+                .iter()
+                .all(|prerequisite| castle.researched.contains(prerequisite))
+            {
+                "available"
+            } else {
+                "locked"
+            };
+            let row = 2 + i;
+            self.module
+                .draw_text_in_row(&format!("{:?}: {}", tech, status), row);
+            if active == Some(*tech) {
+                self.module
+                    .draw_cell(SELECTION_TERMCELL, TermCoord::new(row, selection_col));
+            }
+        }
+
+        let details_row = 3 + TechType::COUNT;
+        let Some(tech) = active else {
+            self.module
+                .draw_text_in_row("e: pick a tech to research", details_row);
+            return;
+        };
+        let prerequisites = tech
+            .prerequisites()
+            .iter()
+            .map(|prerequisite| format!("{:?}", prerequisite))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let details = [
+            tech.description().to_string(),
+            format!("Cost: {}", tech.cost()),
+            format!("Ticks: {}", tech.research_ticks()),
+            format!(
+                "Needs: {}",
+                if prerequisites.is_empty() {
+                    "nothing".to_string()
+                } else {
+                    prerequisites
+                }
+            ),
+            String::new(),
+            "enter: research | esc: back".to_string(),
+        ];
+        for (i, text) in details.iter().enumerate() {
+            self.module.draw_text_in_row(text, details_row + i);
+        }
+    }
+
     pub fn draw_logs_tab(&mut self, logs: &Logs) {
         let drawable_size = self.module.drawable_size();
         let tab_width = 4;
 
-        let mut all_lines: Vec<String> = Vec::new();
+        let mut all_lines: Vec<(String, Color)> = Vec::new();
 
-        for log in logs.content.iter().rev().take(drawable_size.y) {
+        for (log, severity) in logs.content.iter().rev().take(drawable_size.y) {
+            let color = match severity {
+                Severity::Info => WHITE,
+                Severity::Warning => YELLOW,
+                Severity::Danger => RED,
+            };
             if log.len() <= drawable_size.x {
-                all_lines.push(log.clone());
+                all_lines.push((log.clone(), color));
             } else {
                 let chars: Vec<char> = log.chars().collect();
                 let mut pos = 0;
@@ -115,7 +242,7 @@ impl ModPlayerInfo {
                         line.push_str(&" ".repeat(tab_width));
                     }
                     line.extend(&chars[pos..end]);
-                    all_lines.push(line);
+                    all_lines.push((line, color));
 
                     pos = end;
                     is_first_line = false;
@@ -123,11 +250,11 @@ impl ModPlayerInfo {
             }
         }
 
-        for (line_row, line) in all_lines.iter().enumerate() {
+        for (line_row, (line, color)) in all_lines.iter().enumerate() {
             for (char_col, ch) in line.chars().enumerate() {
                 if line_row < drawable_size.y {
                     let pos = TermCoord::new(drawable_size.y - line_row - 1, char_col);
-                    self.module.draw_cell(TermCell::new(ch, WHITE, BLACK), pos);
+                    self.module.draw_cell(TermCell::new(ch, *color, BLACK), pos);
                 }
             }
         }
@@ -12,7 +12,9 @@ use common::{
     r#const::MAX_LOBBIES,
     courtyard::{Facility, FacilityType},
     game_objs::GameObjE,
-    units::UnitGroup,
+    research::TechType,
+    resources::Resources,
+    units::{UnitGroup, UnitType},
 };
 use crossterm::{
     ExecutableCommand, cursor,
@@ -36,10 +38,27 @@ use tokio::{
 pub enum T2C {
     NewCastle(GameCoord),
     AttackCastle(GameId, UnitGroup),
-    SendUnits(GameCoord, UnitGroup),
+    SendUnits(Vec<GameCoord>, UnitGroup, bool),
+    RecallUnits(GameId),
+    RedirectUnits(GameId, GameCoord),
+    RedirectAttack(GameId, GameId),
+    MergeUnits(GameId, GameId),
+    SplitUnits(GameId, UnitGroup),
+    ReinforceCastle(GameId, UnitGroup),
+    RecallReinforcements(GameId),
+    GiftResources(GameId, Resources),
     InCourtyard,
     OutCourtyard,
     NewFacility((GameCoord, FacilityType)),
+    UpgradeFacility(u8),
+    DemolishFacility(u8),
+    RelocateFacility(u8, GameCoord),
+    AssignWorkers(u8, u32),
+    UpgradeCastle,
+    Research(TechType),
+    TrainUnits(UnitType, u32),
+    CancelTraining(u32),
+    GrantKit(GameId, String),
 }
 
 pub struct Tui {
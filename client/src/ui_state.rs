@@ -1,11 +1,14 @@
 use common::{
-    GameCoord, GameId, all_facilities,
+    GameCoord, GameId, all_facilities, all_resources, all_techs,
     r#const::{COURTYARD_COLS, COURTYARD_ROWS, MAP_COLS, MAP_ROWS},
     courtyard::FacilityType,
+    game_objs::GameObjE,
+    research::TechType,
+    resources::{ResourceType, Resources},
     units::{UnitGroup, UnitType},
 };
 
-use crate::{camera::Camera, coord::TermCoord};
+use crate::{camera::Camera, coord::TermCoord, game_state::GameState};
 use crate::{camera::CameraLocation, renderer::ModPlayerInfoTab};
 
 pub struct UiState {
@@ -13,6 +16,8 @@ pub struct UiState {
     pub tab: ModPlayerInfoTab,
     pub mode: UiMode,
     pub term_size_change: Option<TermCoord>,
+    // Dropped in inspect mode, used by the next units sent to a map position
+    pub waypoints: Vec<GameCoord>,
 }
 
 impl UiState {
@@ -22,6 +27,7 @@ impl UiState {
             tab: ModPlayerInfoTab::Castle,
             mode: UiMode::Std,
             term_size_change: None,
+            waypoints: Vec::new(),
         }
     }
 
@@ -58,11 +64,33 @@ pub enum UiMode {
     Inspect(Inspect),
     UnitSelection(UnitSelection),
     FacilitySelection(FacilitySelection),
+    ResourceSelection(ResourceSelection),
+    TrainingSelection(TrainingSelection),
+    KitGrant(KitGrant),
+    ResearchSelection(ResearchSelection),
 }
 
 pub struct Inspect {
     pub coord: GameCoord,
     pub selection: Option<GameId>,
+    // Deployed units waiting for a new destination
+    pub redirect: Option<GameId>,
+    // Deployed units waiting for the units to join
+    pub merge: Option<GameId>,
+    // Facility waiting for its new position in the courtyard
+    pub relocate: Option<u8>,
+}
+
+impl Inspect {
+    pub fn new(coord: GameCoord) -> Self {
+        Self {
+            coord,
+            selection: None,
+            redirect: None,
+            merge: None,
+            relocate: None,
+        }
+    }
 }
 
 #[derive(Clone)]
@@ -77,14 +105,44 @@ pub struct UnitSelection {
     pub interact_target: InteractTarget,
     pub active_input: (UnitType, Option<String>),
     pub selected_units: UnitGroup,
+    pub patrol: bool,
+    // Deployed units to split, the castle garrison if None
+    pub source: Option<GameId>,
+    // The units join the target castle instead of attacking it
+    pub reinforce: bool,
 }
 
 impl UnitSelection {
     pub fn from_interact(interact_target: InteractTarget) -> Self {
         Self {
             interact_target,
-            active_input: (UnitType::form_index(0), None),
+            active_input: (UnitType::from_index(0), None),
             selected_units: UnitGroup::new(),
+            patrol: false,
+            source: None,
+            reinforce: false,
+        }
+    }
+
+    pub fn for_reinforce(castle_id: GameId) -> Self {
+        let mut selection = Self::from_interact(InteractTarget::GameObj(castle_id));
+        selection.reinforce = true;
+        selection
+    }
+
+    pub fn for_split(units_id: GameId) -> Self {
+        let mut selection = Self::from_interact(InteractTarget::GameObj(units_id));
+        selection.source = Some(units_id);
+        selection
+    }
+
+    pub fn get_available<'a>(&self, game_state: &'a GameState) -> Option<&'a UnitGroup> {
+        match self.source {
+            Some(units_id) => match game_state.objs.get(&units_id) {
+                Some(GameObjE::DeployedUnits(units)) => units.units.as_ref(),
+                _ => None,
+            },
+            None => game_state.castle.as_ref().map(|castle| &castle.units),
         }
     }
 }
@@ -102,3 +160,64 @@ impl FacilitySelection {
         }
     }
 }
+
+pub struct ResourceSelection {
+    pub target: GameId,
+    pub active_input: (ResourceType, Option<String>),
+    pub selected: Resources,
+}
+
+impl ResourceSelection {
+    pub fn new(target: GameId) -> Self {
+        Self {
+            target,
+            active_input: (all_resources!()[0], None),
+            selected: Resources::new(),
+        }
+    }
+}
+
+pub struct TrainingSelection {
+    pub facility_id: u8,
+    // Units trained by the facility
+    pub units: Vec<UnitType>,
+    pub active_input: (usize, Option<String>),
+}
+
+impl TrainingSelection {
+    pub fn new(facility_id: u8, facility_type: FacilityType) -> Self {
+        Self {
+            facility_id,
+            units: facility_type.trainable_units(),
+            active_input: (0, None),
+        }
+    }
+}
+
+// Admin command, the kit name is typed by hand
+pub struct KitGrant {
+    pub target: GameId,
+    pub kit: String,
+}
+
+impl KitGrant {
+    pub fn new(target: GameId) -> Self {
+        Self {
+            target,
+            kit: String::new(),
+        }
+    }
+}
+
+// Browsing the techs in the research tab
+pub struct ResearchSelection {
+    pub active: TechType,
+}
+
+impl ResearchSelection {
+    pub fn new() -> Self {
+        Self {
+            active: all_techs!()[0],
+        }
+    }
+}
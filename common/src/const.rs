@@ -6,16 +6,39 @@ pub const MAP_COLS: usize = 64 * 16;
 pub const COURTYARD_ROWS: usize = 60;
 pub const COURTYARD_COLS: usize = 60;
 
+pub const CASTLE_MAX_LV: u32 = 5;
+// Side of the buildable courtyard square of a lv 1 castle, grown each level
+pub const COURTYARD_BASE_SIDE: usize = 30;
+pub const COURTYARD_SIDE_PER_LV: usize = 8;
+
 pub const MAX_LOBBY_PLAYERS: usize = 15;
 pub const MAX_LOBBIES: usize = 10;
 
 pub const ONLINE: bool = false;
 pub const IP_LOCAL: &str = "127.0.0.1:7878";
+// Environment variable with the admin token. The server grants admin rights to
+// the clients logging in with the same token, nobody is admin if it's unset.
+pub const ADMIN_TOKEN_VAR: &str = "CASTLI_ADMIN_TOKEN";
 
 pub const KNIGHT_STR: u32 = 1;
 pub const MAGE_STR: u32 = 3;
 pub const DRAGON_STR: u32 = 10;
 pub const SHIP_STR: u32 = 0;
 
+// Waypoints of a single route, destination included
+pub const MAX_WAYPOINTS: usize = 8;
+
+// Land units a single ship can carry
+pub const SHIP_CAPACITY: u32 = 20;
+
+// Ticks spent on a tile when entering it. Grass must stay the cheapest tile,
+// since the pathfinding heuristic assumes it.
+pub const GRASS_MOVE_COST: u32 = 1;
+pub const WOODS_MOVE_COST: u32 = 2;
+pub const MOUNTAIN_MOVE_COST: u32 = 4;
+pub const WATER_MOVE_COST: u32 = 1;
+// Extra ticks to board or leave the ships on a coast tile
+pub const EMBARK_COST: u32 = 3;
+
 pub const CASTLE_SIZE: GameCoord = GameCoord::new(2, 1);
 pub const FARM_PLOT_SIZE: GameCoord = GameCoord::new(8, 6);
@@ -1,6 +1,13 @@
 use serde::{Deserialize, Serialize};
 
-use crate::{GameCoord, Resources, r#const::FARM_PLOT_SIZE};
+use crate::{
+    GameCoord, all_units,
+    r#const::{
+        COURTYARD_BASE_SIDE, COURTYARD_COLS, COURTYARD_ROWS, COURTYARD_SIDE_PER_LV, FARM_PLOT_SIZE,
+    },
+    resources::{ResourceType, Resources},
+    units::UnitType,
+};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum FacilityType {
@@ -9,6 +16,9 @@ pub enum FacilityType {
     Mines,
     Barracks,
     Shipyard,
+    MageTower,
+    DragonRoost,
+    Warehouse,
 }
 
 #[macro_export]
@@ -20,31 +30,127 @@ macro_rules! all_facilities {
             $crate::courtyard::FacilityType::Mines,
             $crate::courtyard::FacilityType::Barracks,
             $crate::courtyard::FacilityType::Shipyard,
+            $crate::courtyard::FacilityType::MageTower,
+            $crate::courtyard::FacilityType::DragonRoost,
+            $crate::courtyard::FacilityType::Warehouse,
         ]
     };
 }
 
 impl FacilityType {
-    pub const COUNT: usize = 5;
+    pub const COUNT: usize = 8;
 
-    pub fn max_count(&self) -> u8 {
+    // Castle level needed to build the facility
+    pub fn unlock_lv(&self) -> u32 {
         match self {
-            FacilityType::FarmPlot => 4,
+            FacilityType::FarmPlot => 1,
             FacilityType::Sawmill => 1,
             FacilityType::Mines => 1,
             FacilityType::Barracks => 1,
+            FacilityType::Warehouse => 1,
+            FacilityType::MageTower => 2,
+            FacilityType::Shipyard => 3,
+            FacilityType::DragonRoost => 4,
+        }
+    }
+
+    // 0 while the facility is locked
+    pub fn max_count(&self, castle_lv: u32) -> u8 {
+        if castle_lv < self.unlock_lv() {
+            return 0;
+        }
+        let castle_lv = castle_lv as u8;
+        match self {
+            FacilityType::FarmPlot => 2 + castle_lv,
+            FacilityType::Sawmill => 1 + castle_lv / 3,
+            FacilityType::Mines => 1 + castle_lv / 3,
+            FacilityType::Barracks => 1,
             FacilityType::Shipyard => 1,
+            FacilityType::MageTower => 1,
+            FacilityType::DragonRoost => 1,
+            FacilityType::Warehouse => 1 + castle_lv / 2,
         }
     }
 
     pub fn base_cost(&self) -> Resources {
         match self {
-            FacilityType::FarmPlot => Resources::new(4, 4),
-            FacilityType::Sawmill => Resources::new(2, 2),
-            FacilityType::Mines => Resources::new(2, 2),
-            FacilityType::Barracks => Resources::new(50, 50),
-            FacilityType::Shipyard => Resources::new(1000, 1000),
+            FacilityType::FarmPlot => {
+                Resources::from_pairs(&[(ResourceType::Wood, 4), (ResourceType::Stone, 4)])
+            }
+            FacilityType::Sawmill => {
+                Resources::from_pairs(&[(ResourceType::Wood, 2), (ResourceType::Stone, 2)])
+            }
+            FacilityType::Mines => {
+                Resources::from_pairs(&[(ResourceType::Wood, 2), (ResourceType::Stone, 2)])
+            }
+            FacilityType::Barracks => Resources::from_pairs(&[
+                (ResourceType::Wood, 50),
+                (ResourceType::Stone, 50),
+                (ResourceType::Iron, 20),
+            ]),
+            FacilityType::Shipyard => Resources::from_pairs(&[
+                (ResourceType::Wood, 1000),
+                (ResourceType::Stone, 1000),
+                (ResourceType::Iron, 100),
+            ]),
+            FacilityType::MageTower => Resources::from_pairs(&[
+                (ResourceType::Wood, 200),
+                (ResourceType::Stone, 300),
+                (ResourceType::Gold, 50),
+            ]),
+            FacilityType::DragonRoost => Resources::from_pairs(&[
+                (ResourceType::Wood, 800),
+                (ResourceType::Stone, 800),
+                (ResourceType::Gold, 200),
+                (ResourceType::Iron, 200),
+            ]),
+            FacilityType::Warehouse => {
+                Resources::from_pairs(&[(ResourceType::Wood, 100), (ResourceType::Stone, 50)])
+            }
+        }
+    }
+
+    pub fn max_lv(&self) -> u32 {
+        match self {
+            FacilityType::FarmPlot => 5,
+            FacilityType::Sawmill => 10,
+            FacilityType::Mines => 10,
+            FacilityType::Barracks => 5,
+            FacilityType::Shipyard => 3,
+            FacilityType::MageTower => 5,
+            FacilityType::DragonRoost => 3,
+            FacilityType::Warehouse => 5,
+        }
+    }
+
+    // Cost to bring a facility from lv to lv + 1
+    pub fn upgrade_cost(&self, lv: u32) -> Resources {
+        let factor = (lv + 1).saturating_mul(lv + 1);
+        self.base_cost().times(factor)
+    }
+
+    // Everything paid to get a facility to the given level
+    pub fn total_cost(&self, lv: u32) -> Resources {
+        let mut total = self.base_cost();
+        for paid_lv in 1..lv {
+            total.saturating_add(&self.upgrade_cost(paid_lv));
         }
+        total
+    }
+
+    // Ticks needed to bring a facility to the given level, 1 being the construction
+    pub fn build_ticks(&self, lv: u32) -> u32 {
+        let base = match self {
+            FacilityType::FarmPlot => 10,
+            FacilityType::Sawmill => 15,
+            FacilityType::Mines => 15,
+            FacilityType::Barracks => 30,
+            FacilityType::Shipyard => 60,
+            FacilityType::MageTower => 45,
+            FacilityType::DragonRoost => 90,
+            FacilityType::Warehouse => 20,
+        };
+        base * lv.max(1)
     }
 
     pub fn size(&self) -> GameCoord {
@@ -54,9 +160,29 @@ impl FacilityType {
             FacilityType::Mines => GameCoord::new(6, 7),
             FacilityType::Barracks => GameCoord::new(8, 9),
             FacilityType::Shipyard => GameCoord::new(8, 11),
+            FacilityType::MageTower => GameCoord::new(8, 7),
+            FacilityType::DragonRoost => GameCoord::new(8, 11),
+            FacilityType::Warehouse => GameCoord::new(6, 7),
+        }
+    }
+
+    // Peasants each level can employ, 0 for facilities that don't need staff
+    pub fn workers_per_lv(&self) -> u32 {
+        match self {
+            FacilityType::FarmPlot => 2,
+            FacilityType::Sawmill => 5,
+            FacilityType::Mines => 5,
+            _ => 0,
         }
     }
 
+    pub fn trainable_units(&self) -> Vec<UnitType> {
+        all_units!()
+            .into_iter()
+            .filter(|unit| unit.trained_at() == Some(*self))
+            .collect()
+    }
+
     pub fn as_index(&self) -> usize {
         *self as usize
     }
@@ -66,15 +192,131 @@ impl FacilityType {
     }
 }
 
+// Cost to bring a castle from lv to lv + 1
+pub fn castle_upgrade_cost(lv: u32) -> Resources {
+    let base = Resources::from_pairs(&[
+        (ResourceType::Wood, 200),
+        (ResourceType::Stone, 300),
+        (ResourceType::Gold, 50),
+    ]);
+    base.times(lv.saturating_mul(lv))
+}
+
+// Top left corner and size of the buildable area, centered in the courtyard
+pub fn courtyard_area(castle_lv: u32) -> (GameCoord, GameCoord) {
+    let grown = COURTYARD_SIDE_PER_LV * castle_lv.saturating_sub(1) as usize;
+    let rows = (COURTYARD_BASE_SIDE + grown).min(COURTYARD_ROWS);
+    let cols = (COURTYARD_BASE_SIDE + grown).min(COURTYARD_COLS);
+    // Even corners, like the positions picked in the courtyard
+    let corner = GameCoord::new(
+        ((COURTYARD_ROWS - rows) / 2) & !1,
+        ((COURTYARD_COLS - cols) / 2) & !1,
+    );
+    (corner, GameCoord::new(rows, cols))
+}
+
+// A facility at lv 0 is still under construction
 #[derive(Clone, Serialize, Deserialize)]
 pub struct Facility {
     pub lv: u32,
     pub pos: GameCoord,
     pub r#type: FacilityType,
+    // Peasants assigned to the facility
+    pub workers: u32,
 }
 
 impl Facility {
     pub fn new(r#type: FacilityType, pos: GameCoord) -> Self {
-        Self { lv: 1, r#type, pos }
+        Self {
+            lv: 0,
+            r#type,
+            pos,
+            workers: 0,
+        }
+    }
+
+    pub fn max_workers(&self) -> u32 {
+        self.lv * self.r#type.workers_per_lv()
+    }
+
+    // Scales the production of a fully staffed facility to the workers assigned
+    pub fn staffed(&self, amount: u32) -> u32 {
+        let max_workers = self.max_workers();
+        if max_workers == 0 {
+            return 0;
+        }
+        amount * self.workers.min(max_workers) / max_workers
+    }
+
+    pub fn is_built(&self) -> bool {
+        self.lv > 0
+    }
+
+    pub fn is_max_lv(&self) -> bool {
+        self.lv >= self.r#type.max_lv()
+    }
+
+    pub fn upgrade_cost(&self) -> Resources {
+        self.r#type.upgrade_cost(self.lv)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum BuildKind {
+    Construction,
+    Upgrade,
+}
+
+// Work waiting in the castle build queue, only the first jobs progress
+#[derive(Clone, Serialize, Deserialize)]
+pub struct BuildJob {
+    pub facility_id: u8,
+    pub kind: BuildKind,
+    pub ticks_left: u32,
+    pub total_ticks: u32,
+}
+
+impl BuildJob {
+    pub fn new(facility_id: u8, kind: BuildKind, total_ticks: u32) -> Self {
+        Self {
+            facility_id,
+            kind,
+            ticks_left: total_ticks,
+            total_ticks,
+        }
+    }
+
+    pub fn progress_percent(&self) -> u32 {
+        if self.total_ticks == 0 {
+            return 100;
+        }
+        (self.total_ticks - self.ticks_left) * 100 / self.total_ticks
+    }
+}
+
+// Units ordered at a training facility, trained one at a time
+#[derive(Clone, Serialize, Deserialize)]
+pub struct TrainingJob {
+    pub id: u32,
+    pub unit: UnitType,
+    pub count: u32,
+    pub trained: u32,
+    // Ticks left for the unit in training
+    pub ticks_left: u32,
+}
+
+impl TrainingJob {
+    pub fn new(id: u32, unit: UnitType, count: u32) -> Self {
+        Self {
+            id,
+            unit,
+            count,
+            trained: 0,
+            ticks_left: unit.training_ticks(),
+        }
+    }
+
+    pub fn remaining(&self) -> u32 {
+        self.count - self.trained
     }
 }
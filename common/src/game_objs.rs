@@ -1,6 +1,13 @@
+use std::collections::HashMap;
+
 use serde::{Deserialize, Serialize};
 
-use crate::{GameCoord, GameId, Resources, units::UnitGroup};
+use crate::{
+    GameCoord, GameId,
+    research::{ResearchJob, TechType},
+    resources::Resources,
+    units::UnitGroup,
+};
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub enum GameObjE {
@@ -24,6 +31,7 @@ pub struct CastleE {
     pub name: String,
     pub pos: GameCoord,
     pub alive: bool,
+    pub lv: u32,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -42,13 +50,40 @@ pub enum StructureType {
 pub struct DeployedUnitsE {
     pub owner_id: GameId,
     pub pos: GameCoord,
+    pub embarked: bool,
+    pub status: UnitsStatus,
+    // Only sent to the owner
+    pub units: Option<UnitGroup>,
+    // Waypoints still to reach, only sent to the owner
+    pub waypoints: Option<Vec<GameCoord>>,
+    // Resources carried back home, only sent to the owner
+    pub loot: Option<Resources>,
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, PartialEq)]
+pub enum UnitsStatus {
+    PlanningRoute,
+    Marching,
+    Stationed,
+    Patrolling,
+    Returning,
 }
 
 #[derive(Serialize, Deserialize)]
 pub struct OwnedCastleE {
     pub alive: bool,
+    pub lv: u32,
     pub name: String,
     pub pos: GameCoord,
     pub units: UnitGroup,
     pub resources: Resources,
+    // Units stationed in other castles, by castle
+    pub lent_units: HashMap<GameId, UnitGroup>,
+    // Food produced minus food eaten each tick
+    pub food_balance: i32,
+    pub storage_cap: Resources,
+    // Paid each tick for all the units, the ones away included
+    pub upkeep: Resources,
+    pub researched: Vec<TechType>,
+    pub research_queue: Vec<ResearchJob>,
 }
@@ -4,6 +4,8 @@ pub mod game_objs;
 pub mod map;
 pub mod packets;
 pub mod player;
+pub mod research;
+pub mod resources;
 pub mod stream;
 pub mod units;
 
@@ -68,38 +70,3 @@ impl Time {
         }
     }
 }
-
-#[derive(Serialize, Deserialize, Clone)]
-pub struct Resources {
-    pub wood: u32,
-    pub stone: u32,
-}
-
-impl Resources {
-    pub const fn new(wood: u32, stone: u32) -> Self {
-        Self { wood, stone }
-    }
-
-    pub fn saturating_add(&mut self, other: &Self) {
-        self.wood = self.wood.saturating_add(other.wood);
-        self.stone = self.stone.saturating_add(other.stone);
-    }
-
-    pub fn saturating_sub(&mut self, other: &Self) {
-        self.wood = self.wood.saturating_sub(other.wood);
-        self.stone = self.stone.saturating_sub(other.stone);
-    }
-
-    pub fn subtract_if_enough(&mut self, other: &Self) -> bool {
-        if self.wood >= other.wood && self.stone >= other.stone {
-            self.saturating_sub(other);
-            true
-        } else {
-            false
-        }
-    }
-
-    pub fn contains(&self, other: &Self) -> bool {
-        self.wood >= other.wood && self.stone >= other.stone
-    }
-}
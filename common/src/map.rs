@@ -1,5 +1,7 @@
 use serde::{Deserialize, Serialize};
 
+use crate::r#const::{GRASS_MOVE_COST, MOUNTAIN_MOVE_COST, WOODS_MOVE_COST};
+
 #[derive(Clone, Copy, PartialEq, Serialize, Deserialize, Debug)]
 pub enum Tile {
     Water,
@@ -9,3 +11,15 @@ pub enum Tile {
     HighMountain,
     Err,
 }
+
+impl Tile {
+    // None if ground units can't walk on the tile
+    pub fn move_cost(&self) -> Option<u32> {
+        match self {
+            Tile::Grass => Some(GRASS_MOVE_COST),
+            Tile::Woods => Some(WOODS_MOVE_COST),
+            Tile::Mountain => Some(MOUNTAIN_MOVE_COST),
+            Tile::Water | Tile::HighMountain | Tile::Err => None,
+        }
+    }
+}
@@ -4,15 +4,18 @@ use serde::{Deserialize, Serialize};
 
 use crate::{
     GameCoord, GameId, Time,
-    courtyard::{Facility, FacilityType},
+    courtyard::{BuildJob, Facility, FacilityType, TrainingJob},
     game_objs::{GameObjE, OwnedCastleE},
     map::Tile,
     player::PlayerE,
-    units::UnitGroup,
+    research::TechType,
+    resources::Resources,
+    units::{UnitGroup, UnitType},
 };
 
 /// Represents messages sent from the Server to the Client (S2C).
 #[derive(Serialize, Deserialize)]
+#[allow(clippy::large_enum_variant)]
 pub enum S2C {
     LobbyFound,
     LobbyFull,
@@ -35,15 +38,174 @@ pub struct CourtyardPacket {
     pub player: PlayerE,
     pub castle: OwnedCastleE,
     pub facilities: HashMap<u8, Facility>,
+    pub build_queue: Vec<BuildJob>,
+    pub training_queue: Vec<TrainingJob>,
+    pub peasants: u32,
 }
 
-// TODO: Change this name to Feedback or something similar
+// Something the player should know about, shown in the logs
 #[derive(Serialize, Deserialize)]
-pub enum LogE {
-    CastleCreationErr,
-    UnitDeployErr,
-    AttackDeployErr,
-    FacilityCreationErr,
+pub enum EventE {
+    RouteErr(RouteErr),
+    ReinforcementsArrived {
+        from: String,
+        units: UnitGroup,
+    },
+    GiftReceived {
+        from: String,
+        resources: Resources,
+    },
+    // A construction or upgrade is done, lv 1 being a new facility
+    FacilityBuilt {
+        r#type: FacilityType,
+        lv: u32,
+    },
+    UnitsTrained {
+        unit: UnitType,
+        count: u32,
+    },
+    TechResearched {
+        tech: TechType,
+    },
+    // Resources lost for lack of storage since the last report
+    ResourcesWasted {
+        wasted: Resources,
+    },
+    // Not enough resources to pay the units, deployed ones included
+    UpkeepUnpaid {
+        deserters: UnitGroup,
+    },
+    // Not enough food for peasants and garrison
+    Starvation {
+        peasants: u32,
+        deserters: UnitGroup,
+    },
+    FieldBattle {
+        pos: GameCoord,
+        outcome: BattleOutcome,
+        losses: UnitGroup,
+        enemy_losses: UnitGroup,
+    },
+    // A hostile army left its castle to attack yours
+    AttackDispatched {
+        attacker: String,
+    },
+    // A hostile army attacking your castle is getting close
+    AttackApproaching {
+        attacker: String,
+        distance: usize,
+    },
+    // Sent to both sides, the outcome is from the receiver point of view
+    CastleBattle {
+        attacker: String,
+        defender: String,
+        outcome: BattleOutcome,
+        losses: UnitGroup,
+        enemy_losses: UnitGroup,
+    },
+    // Your army carried off resources from a castle it attacked
+    Plundered {
+        target: String,
+        loot: Resources,
+    },
+    // A hostile army carried off resources from your castle
+    Pillaged {
+        attacker: String,
+        loot: Resources,
+    },
+    // One of your armies came back home with its loot
+    LootDelivered {
+        loot: Resources,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum Severity {
+    Info,
+    Warning,
+    Danger,
+}
+
+impl EventE {
+    pub fn severity(&self) -> Severity {
+        match self {
+            Self::ReinforcementsArrived { .. }
+            | Self::GiftReceived { .. }
+            | Self::FacilityBuilt { .. }
+            | Self::UnitsTrained { .. }
+            | Self::TechResearched { .. }
+            | Self::Plundered { .. }
+            | Self::LootDelivered { .. } => Severity::Info,
+            Self::FieldBattle { outcome, .. } | Self::CastleBattle { outcome, .. } => match outcome
+            {
+                BattleOutcome::Victory => Severity::Info,
+                BattleOutcome::Retreat => Severity::Warning,
+                BattleOutcome::Defeat => Severity::Danger,
+            },
+            Self::AttackApproaching { .. }
+            | Self::Starvation { .. }
+            | Self::UpkeepUnpaid { .. }
+            | Self::Pillaged { .. } => Severity::Danger,
+            Self::RouteErr(_) | Self::AttackDispatched { .. } | Self::ResourcesWasted { .. } => {
+                Severity::Warning
+            }
+        }
+    }
+}
+
+// Chosen by the client for each command, sent back with the feedback
+pub type RequestId = u32;
+
+// Why the lobby refused a command
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum Rejection {
+    NoCastle,
+    AlreadyHasCastle,
+    NotEvenCoord,
+    OccupiedPos,
+    OutOfBounds,
+    NotEnoughResources,
+    NotEnoughUnits,
+    MaxFacilitiesReached,
+    MaxLevelReached,
+    MaxWorkersReached,
+    // The castle level is too low for the facility, or a tech is missing
+    FacilityLocked,
+    AlreadyResearched,
+    MissingPrerequisite,
+    ResearchQueueFull,
+    // The facility already has a job in the build queue, or is not built yet
+    AlreadyBuilding,
+    BuildQueueFull,
+    // No built facility can train the unit
+    MissingFacility,
+    NotEnoughPeasants,
+    TrainingQueueFull,
+    NotAdmin,
+    UnknownKit,
+    InvalidTarget,
+    NotYourUnits,
+    // The units are still waiting for their first route
+    UnitsNotDeployed,
+    TooFar,
+    CannotSail,
+    TooManyWaypoints,
+    NothingToRecall,
+    Route(RouteErr),
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum RouteErr {
+    Unreachable,
+    TargetObstacle,
+    Timeout,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum BattleOutcome {
+    Victory,
+    Retreat,
+    Defeat,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -76,14 +238,16 @@ pub enum L2S4C {
     MainPacket(MainPacket),
     CourtyardPacket(CourtyardPacket),
     Map(MapPayload),
-    Log(LogE),
+    Event(EventE),
+    Feedback(RequestId, Result<(), Rejection>),
 }
 
 // Represents messages sent from the Client to the Server (C2S).
 #[derive(Serialize, Deserialize)]
 pub enum C2S {
-    C2S4L(C2S4L),
-    Login(String),
+    C2S4L(RequestId, C2S4L),
+    // User name and admin token
+    Login(String, Option<String>),
     Lobby(usize),
 }
 
@@ -92,8 +256,32 @@ pub enum C2S {
 pub enum C2S4L {
     NewCastle(GameCoord),
     AttackCastle(GameId, UnitGroup),
-    SendUnits(GameCoord, UnitGroup),
+    // Waypoints in order, the last one is the destination.
+    // With the flag set the units patrol the waypoints instead of stopping.
+    SendUnits(Vec<GameCoord>, UnitGroup, bool),
+    RecallUnits(GameId),
+    RedirectUnits(GameId, GameCoord),
+    RedirectAttack(GameId, GameId),
+    // The first units join the second ones
+    MergeUnits(GameId, GameId),
+    SplitUnits(GameId, UnitGroup),
+    // The units join the garrison of another castle, staying yours
+    ReinforceCastle(GameId, UnitGroup),
+    RecallReinforcements(GameId),
+    GiftResources(GameId, Resources),
     InCourtyard,
     OutCourtyard,
     NewFacility((GameCoord, FacilityType)),
+    UpgradeFacility(u8),
+    DemolishFacility(u8),
+    RelocateFacility(u8, GameCoord),
+    UpgradeCastle,
+    // Queues the tech, paying its cost
+    Research(TechType),
+    // Sets the peasants working at the facility
+    AssignWorkers(u8, u32),
+    TrainUnits(UnitType, u32),
+    CancelTraining(u32),
+    // Admin only, adds the named kit to the castle
+    GrantKit(GameId, String),
 }
@@ -0,0 +1,217 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    courtyard::FacilityType,
+    resources::{ResourceType, Resources},
+    units::UnitType,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum TechType {
+    Roads,
+    Cartography,
+    CropRotation,
+    Irrigation,
+    DeepMining,
+    Forging,
+    Steel,
+    Arcana,
+    DragonTaming,
+}
+
+#[macro_export]
+macro_rules! all_techs {
+    () => {
+        [
+            $crate::research::TechType::Roads,
+            $crate::research::TechType::Cartography,
+            $crate::research::TechType::CropRotation,
+            $crate::research::TechType::Irrigation,
+            $crate::research::TechType::DeepMining,
+            $crate::research::TechType::Forging,
+            $crate::research::TechType::Steel,
+            $crate::research::TechType::Arcana,
+            $crate::research::TechType::DragonTaming,
+        ]
+    };
+}
+
+// What a tech gives once researched, percents of the same kind stack
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TechEffect {
+    // Percent of the ticks spent on each tile
+    MoveCostPercent(u32),
+    // Percent of the construction and upgrade costs
+    FacilityCostPercent(FacilityType, u32),
+    // Percent added to the production
+    ProductionBonusPercent(ResourceType, u32),
+    // Percent added to the strength of the unit
+    StrengthBonusPercent(UnitType, u32),
+    // The facility can't be built without the tech
+    UnlockFacility(FacilityType),
+}
+
+impl TechType {
+    pub const COUNT: usize = 9;
+
+    pub fn from_index(i: usize) -> Self {
+        all_techs!()[i]
+    }
+
+    pub fn as_index(&self) -> usize {
+        *self as usize
+    }
+
+    pub fn effect(&self) -> TechEffect {
+        match self {
+            Self::Roads => TechEffect::MoveCostPercent(75),
+            Self::Cartography => TechEffect::MoveCostPercent(75),
+            Self::CropRotation => TechEffect::FacilityCostPercent(FacilityType::FarmPlot, 50),
+            Self::Irrigation => TechEffect::ProductionBonusPercent(ResourceType::Food, 50),
+            Self::DeepMining => TechEffect::ProductionBonusPercent(ResourceType::Gold, 50),
+            Self::Forging => TechEffect::StrengthBonusPercent(UnitType::Knight, 50),
+            Self::Steel => TechEffect::StrengthBonusPercent(UnitType::Knight, 50),
+            Self::Arcana => TechEffect::StrengthBonusPercent(UnitType::Mage, 30),
+            Self::DragonTaming => TechEffect::UnlockFacility(FacilityType::DragonRoost),
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            Self::Roads => "units move faster",
+            Self::Cartography => "units move even faster",
+            Self::CropRotation => "cheaper farms",
+            Self::Irrigation => "farms produce more food",
+            Self::DeepMining => "mines produce more gold",
+            Self::Forging => "stronger knights",
+            Self::Steel => "even stronger knights",
+            Self::Arcana => "stronger mages",
+            Self::DragonTaming => "allows the dragon roost",
+        }
+    }
+
+    // Techs to research first
+    pub fn prerequisites(&self) -> &'static [TechType] {
+        match self {
+            Self::Roads | Self::CropRotation | Self::DeepMining | Self::Forging | Self::Arcana => {
+                &[]
+            }
+            Self::Cartography => &[Self::Roads],
+            Self::Irrigation => &[Self::CropRotation],
+            Self::Steel => &[Self::Forging, Self::DeepMining],
+            Self::DragonTaming => &[Self::Arcana],
+        }
+    }
+
+    pub fn cost(&self) -> Resources {
+        match self {
+            Self::Roads => {
+                Resources::from_pairs(&[(ResourceType::Wood, 100), (ResourceType::Stone, 200)])
+            }
+            Self::Cartography => {
+                Resources::from_pairs(&[(ResourceType::Wood, 300), (ResourceType::Gold, 100)])
+            }
+            Self::CropRotation => {
+                Resources::from_pairs(&[(ResourceType::Wood, 50), (ResourceType::Food, 200)])
+            }
+            Self::Irrigation => {
+                Resources::from_pairs(&[(ResourceType::Stone, 300), (ResourceType::Food, 300)])
+            }
+            Self::DeepMining => {
+                Resources::from_pairs(&[(ResourceType::Wood, 200), (ResourceType::Stone, 200)])
+            }
+            Self::Forging => {
+                Resources::from_pairs(&[(ResourceType::Stone, 100), (ResourceType::Iron, 100)])
+            }
+            Self::Steel => Resources::from_pairs(&[
+                (ResourceType::Stone, 200),
+                (ResourceType::Gold, 50),
+                (ResourceType::Iron, 250),
+            ]),
+            Self::Arcana => {
+                Resources::from_pairs(&[(ResourceType::Wood, 100), (ResourceType::Gold, 150)])
+            }
+            Self::DragonTaming => Resources::from_pairs(&[
+                (ResourceType::Food, 500),
+                (ResourceType::Gold, 200),
+                (ResourceType::Iron, 100),
+            ]),
+        }
+    }
+
+    pub fn research_ticks(&self) -> u32 {
+        match self {
+            Self::Roads | Self::CropRotation => 60,
+            Self::DeepMining | Self::Forging | Self::Arcana => 90,
+            Self::Cartography | Self::Irrigation => 120,
+            Self::Steel | Self::DragonTaming => 180,
+        }
+    }
+}
+
+// Research waiting in the castle queue, only the first one progresses
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ResearchJob {
+    pub tech: TechType,
+    pub ticks_left: u32,
+}
+
+impl ResearchJob {
+    pub fn new(tech: TechType) -> Self {
+        Self {
+            tech,
+            ticks_left: tech.research_ticks(),
+        }
+    }
+
+    pub fn progress_percent(&self) -> u32 {
+        let total_ticks = self.tech.research_ticks().max(1);
+        (total_ticks - self.ticks_left.min(total_ticks)) * 100 / total_ticks
+    }
+}
+
+// The modifiers below are computed from the researched techs, so that the
+// client can show the same costs the server charges
+
+pub fn move_cost_percent(techs: &[TechType]) -> u32 {
+    techs.iter().fold(100, |percent, tech| match tech.effect() {
+        TechEffect::MoveCostPercent(tech_percent) => percent * tech_percent / 100,
+        _ => percent,
+    })
+}
+
+pub fn facility_cost_percent(techs: &[TechType], facility: FacilityType) -> u32 {
+    techs.iter().fold(100, |percent, tech| match tech.effect() {
+        TechEffect::FacilityCostPercent(r#type, tech_percent) if r#type == facility => {
+            percent * tech_percent / 100
+        }
+        _ => percent,
+    })
+}
+
+pub fn production_bonus_percent(techs: &[TechType], resource: ResourceType) -> u32 {
+    techs.iter().fold(0, |bonus, tech| match tech.effect() {
+        TechEffect::ProductionBonusPercent(r#type, tech_bonus) if r#type == resource => {
+            bonus + tech_bonus
+        }
+        _ => bonus,
+    })
+}
+
+// Indexed by unit type
+pub fn strength_bonus_percent(techs: &[TechType]) -> [u32; UnitType::COUNT] {
+    let mut bonus = [0; UnitType::COUNT];
+    for tech in techs.iter() {
+        if let TechEffect::StrengthBonusPercent(unit, tech_bonus) = tech.effect() {
+            bonus[unit.as_index()] += tech_bonus;
+        }
+    }
+    bonus
+}
+
+// The tech unlocking the facility, None if it needs no research
+pub fn required_tech(facility: FacilityType) -> Option<TechType> {
+    all_techs!()
+        .into_iter()
+        .find(|tech| tech.effect() == TechEffect::UnlockFacility(facility))
+}
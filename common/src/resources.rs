@@ -0,0 +1,180 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, PartialEq)]
+pub enum ResourceType {
+    Wood,
+    Stone,
+    Food,
+    Gold,
+    Iron,
+}
+
+#[macro_export]
+macro_rules! all_resources {
+    () => {
+        [
+            $crate::resources::ResourceType::Wood,
+            $crate::resources::ResourceType::Stone,
+            $crate::resources::ResourceType::Food,
+            $crate::resources::ResourceType::Gold,
+            $crate::resources::ResourceType::Iron,
+        ]
+    };
+}
+
+impl ResourceType {
+    pub const COUNT: usize = 5;
+
+    pub fn from_index(i: usize) -> Self {
+        all_resources!()[i]
+    }
+
+    pub fn as_index(&self) -> usize {
+        *self as usize
+    }
+
+    // Storage of a lv 1 castle without warehouses
+    pub fn base_storage(&self) -> u32 {
+        match self {
+            Self::Wood | Self::Stone => 500,
+            Self::Food => 1000,
+            Self::Gold => 200,
+            Self::Iron => 300,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Resources {
+    pub quantities: [u32; ResourceType::COUNT],
+}
+
+impl Default for Resources {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Resources {
+    pub const fn new() -> Self {
+        Self {
+            quantities: [0; ResourceType::COUNT],
+        }
+    }
+
+    // Usable in consts, resources not listed are 0
+    pub const fn from_pairs(pairs: &[(ResourceType, u32)]) -> Self {
+        let mut quantities = [0; ResourceType::COUNT];
+        let mut i = 0;
+        while i < pairs.len() {
+            let (resource, amount) = pairs[i];
+            quantities[resource as usize] += amount;
+            i += 1;
+        }
+        Self { quantities }
+    }
+
+    pub fn get(&self, resource: ResourceType) -> u32 {
+        self.quantities[resource.as_index()]
+    }
+
+    pub fn get_mut(&mut self, resource: ResourceType) -> &mut u32 {
+        &mut self.quantities[resource.as_index()]
+    }
+
+    pub fn add_single_type(&mut self, resource: ResourceType, amount: u32) {
+        let idx = resource.as_index();
+        self.quantities[idx] = self.quantities[idx].saturating_add(amount);
+    }
+
+    pub fn saturating_add(&mut self, other: &Self) {
+        for (i, amount) in other.quantities.iter().enumerate() {
+            self.quantities[i] = self.quantities[i].saturating_add(*amount);
+        }
+    }
+
+    pub fn saturating_sub(&mut self, other: &Self) {
+        for (i, amount) in other.quantities.iter().enumerate() {
+            self.quantities[i] = self.quantities[i].saturating_sub(*amount);
+        }
+    }
+
+    pub fn subtract_if_enough(&mut self, other: &Self) -> bool {
+        if self.contains(other) {
+            self.saturating_sub(other);
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn contains(&self, other: &Self) -> bool {
+        self.quantities
+            .iter()
+            .zip(other.quantities.iter())
+            .all(|(own, other)| own >= other)
+    }
+
+    // Clamps every resource to the cap, returns what did not fit
+    pub fn cap(&mut self, cap: &Self) -> Self {
+        let mut overflow = Self::new();
+        for (i, amount) in self.quantities.iter_mut().enumerate() {
+            if *amount > cap.quantities[i] {
+                overflow.quantities[i] = *amount - cap.quantities[i];
+                *amount = cap.quantities[i];
+            }
+        }
+        overflow
+    }
+
+    pub fn total(&self) -> u32 {
+        self.quantities
+            .iter()
+            .fold(0, |acc, amount| acc.saturating_add(*amount))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.quantities.iter().all(|amount| *amount == 0)
+    }
+
+    pub fn times(&self, factor: u32) -> Self {
+        let mut result = self.clone();
+        for amount in result.quantities.iter_mut() {
+            *amount = amount.saturating_mul(factor);
+        }
+        result
+    }
+
+    pub fn percent(&self, percent: u32) -> Self {
+        let mut result = self.times(percent);
+        for amount in result.quantities.iter_mut() {
+            *amount /= 100;
+        }
+        result
+    }
+
+    pub fn iter_present(&self) -> impl Iterator<Item = (ResourceType, u32)> + '_ {
+        all_resources!()
+            .into_iter()
+            .filter(move |r| self.get(*r) > 0)
+            .map(move |r| (r, self.get(r)))
+    }
+}
+
+// Only the resources present, e.g. "Wood: 4, Stone: 4"
+impl fmt::Display for Resources {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_empty() {
+            return write!(f, "nothing");
+        }
+        for (i, (resource, amount)) in self.iter_present().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{:?}: {}", resource, amount)?;
+        }
+        Ok(())
+    }
+}
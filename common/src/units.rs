@@ -1,6 +1,10 @@
 use serde::{Deserialize, Serialize};
 
-use crate::r#const::{DRAGON_STR, KNIGHT_STR, MAGE_STR, SHIP_STR};
+use crate::{
+    r#const::{DRAGON_STR, KNIGHT_STR, MAGE_STR, SHIP_CAPACITY, SHIP_STR},
+    courtyard::FacilityType,
+    resources::{ResourceType, Resources},
+};
 
 #[derive(Clone, Copy, Serialize, Deserialize, Debug, PartialEq)]
 pub enum UnitType {
@@ -33,16 +37,6 @@ impl UnitType {
         *self as usize
     }
 
-    pub fn form_index(i: usize) -> Self {
-        match i {
-            0 => Self::Knight,
-            1 => Self::Mage,
-            2 => Self::Dragon,
-            3 => Self::Ship,
-            _ => panic!(),
-        }
-    }
-
     pub fn get_strength(&self) -> u32 {
         match self {
             Self::Knight => KNIGHT_STR,
@@ -52,12 +46,104 @@ impl UnitType {
         }
     }
 
+    pub fn get_capacity(&self) -> u32 {
+        match self {
+            Self::Ship => SHIP_CAPACITY,
+            _ => 0,
+        }
+    }
+
+    // Resources a single unit can carry back from a plunder
+    pub fn carry_capacity(&self) -> u32 {
+        match self {
+            Self::Knight => 10,
+            Self::Mage => 5,
+            Self::Dragon => 100,
+            Self::Ship => 200,
+        }
+    }
+
+    // The facility training this unit
+    pub fn trained_at(&self) -> Option<FacilityType> {
+        match self {
+            Self::Knight => Some(FacilityType::Barracks),
+            Self::Mage => Some(FacilityType::MageTower),
+            Self::Dragon => Some(FacilityType::DragonRoost),
+            Self::Ship => Some(FacilityType::Shipyard),
+        }
+    }
+
+    pub fn training_cost(&self) -> Resources {
+        match self {
+            Self::Knight => Resources::from_pairs(&[
+                (ResourceType::Wood, 5),
+                (ResourceType::Stone, 5),
+                (ResourceType::Iron, 1),
+            ]),
+            Self::Mage => Resources::from_pairs(&[
+                (ResourceType::Wood, 20),
+                (ResourceType::Stone, 10),
+                (ResourceType::Gold, 5),
+            ]),
+            Self::Dragon => Resources::from_pairs(&[
+                (ResourceType::Wood, 100),
+                (ResourceType::Stone, 100),
+                (ResourceType::Gold, 50),
+                (ResourceType::Iron, 20),
+            ]),
+            Self::Ship => Resources::from_pairs(&[(ResourceType::Wood, 50)]),
+        }
+    }
+
+    // Tenths of food eaten each tick
+    pub fn food_tenths(&self) -> u32 {
+        match self {
+            Self::Knight | Self::Mage => 1,
+            Self::Dragon => 10,
+            Self::Ship => 0,
+        }
+    }
+
+    // Tenths of resources paid each tick, wherever the unit is
+    pub fn upkeep_tenths(&self) -> Resources {
+        match self {
+            Self::Knight => Resources::from_pairs(&[(ResourceType::Gold, 1)]),
+            Self::Mage => Resources::from_pairs(&[(ResourceType::Gold, 3)]),
+            Self::Dragon => {
+                Resources::from_pairs(&[(ResourceType::Gold, 20), (ResourceType::Iron, 5)])
+            }
+            Self::Ship => Resources::from_pairs(&[(ResourceType::Wood, 5)]),
+        }
+    }
+
+    pub fn training_peasants(&self) -> u32 {
+        match self {
+            Self::Knight | Self::Mage => 1,
+            Self::Dragon => 2,
+            Self::Ship => 5,
+        }
+    }
+
+    // Ticks to train a single unit at a lv 1 facility
+    pub fn training_ticks(&self) -> u32 {
+        match self {
+            Self::Knight => 5,
+            Self::Mage => 15,
+            Self::Dragon => 60,
+            Self::Ship => 30,
+        }
+    }
+
+    pub fn is_naval(&self) -> bool {
+        matches!(self, Self::Ship)
+    }
+
     pub fn as_mask(&self) -> u8 {
         1 << self.as_index()
     }
 }
 
-#[derive(Serialize, Deserialize, Clone)]
+#[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct UnitGroup {
     pub quantities: [u32; UnitType::COUNT],
 }
@@ -78,6 +164,49 @@ impl UnitGroup {
         str
     }
 
+    pub fn get_carry_capacity(&self) -> u32 {
+        self.iter_present()
+            .map(|(unit, count)| count.saturating_mul(unit.carry_capacity()))
+            .fold(0, u32::saturating_add)
+    }
+
+    // Resources paid each tick for the whole group, rounded up
+    pub fn get_upkeep(&self) -> Resources {
+        let mut tenths = Resources::new();
+        for (unit, count) in self.iter_present() {
+            tenths.saturating_add(&unit.upkeep_tenths().times(count));
+        }
+        for amount in tenths.quantities.iter_mut() {
+            *amount = amount.div_ceil(10);
+        }
+        tenths
+    }
+
+    // Bonus percents indexed by unit type
+    pub fn get_boosted_strength(&self, bonus: &[u32; UnitType::COUNT]) -> u32 {
+        let mut str: u64 = 0;
+        // Applied to the whole stack, a lone knight is too weak to round up
+        for (unit, count) in self.iter_present() {
+            let percent = 100 + bonus[unit.as_index()] as u64;
+            str += count as u64 * unit.get_strength() as u64 * percent / 100;
+        }
+        str.min(u32::MAX as u64) as u32
+    }
+
+    // Every land unit needs a seat on one of the ships to cross water
+    pub fn can_sail(&self) -> bool {
+        let mut capacity: u32 = 0;
+        let mut passengers: u32 = 0;
+        for (unit, count) in self.iter_present() {
+            if unit.is_naval() {
+                capacity = capacity.saturating_add(count.saturating_mul(unit.get_capacity()));
+            } else {
+                passengers = passengers.saturating_add(count);
+            }
+        }
+        capacity > 0 && passengers <= capacity
+    }
+
     pub fn add_single_type(&mut self, unit: UnitType, count: u32) {
         let idx = unit.as_index();
         self.quantities[idx] = self.quantities[idx].saturating_add(count);
@@ -93,20 +222,20 @@ impl UnitGroup {
             return false;
         }
         for (i, quantity) in other.quantities.iter().enumerate() {
-            self.subtract_single_type(UnitType::form_index(i), *quantity);
+            self.subtract_single_type(UnitType::from_index(i), *quantity);
         }
         true
     }
 
     pub fn subtract_unchecked(&mut self, other: &Self) {
         for (i, quantity) in other.quantities.iter().enumerate() {
-            self.subtract_single_type(UnitType::form_index(i), *quantity);
+            self.subtract_single_type(UnitType::from_index(i), *quantity);
         }
     }
 
     pub fn saturating_add(&mut self, other: &Self) {
         for (i, quantity) in other.quantities.iter().enumerate() {
-            self.add_single_type(UnitType::form_index(i), *quantity);
+            self.add_single_type(UnitType::from_index(i), *quantity);
         }
     }
 
@@ -0,0 +1,28 @@
+use std::{
+    fs::OpenOptions,
+    io::Write,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use common::packets::Rejection;
+
+use crate::r#const::ADMIN_AUDIT_LOG;
+
+// Every admin command is appended to the audit log, refused ones included
+pub fn log_admin_command(admin: &str, command: &str, result: &Result<(), Rejection>) {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0);
+    let line = format!("{} {} {} {:?}\n", timestamp, admin, command, result);
+    print!("[audit] {}", line);
+
+    let written = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(ADMIN_AUDIT_LOG)
+        .and_then(|mut file| file.write_all(line.as_bytes()));
+    if let Err(err) = written {
+        println!("[audit] Could not write to {}: {}", ADMIN_AUDIT_LOG, err);
+    }
+}
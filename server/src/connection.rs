@@ -1,23 +1,22 @@
 use std::{
     io::{Read, Write},
     net::TcpStream,
-    sync::mpsc::{Receiver, Sender},
 };
 
 use bincode::{config, serde::decode_from_slice, serde::encode_to_vec};
 
 use common::{
-    packets::{C2S, C2S4L, L2S4C, S2C},
+    packets::{C2S, S2C},
     stream::{MAX_FRAME_BYTES, StreamErr},
 };
 
-use crate::server::{Client, ConnId};
+use crate::server::{Client, ConnId, LobbyLink};
 
 pub struct Connection {
     pub stream: TcpStream,
     pub read_buffer: Vec<u8>,
     pub write_buffer: Vec<u8>,
-    pub lobby_link: Option<(Sender<C2S4L>, Receiver<L2S4C>)>,
+    pub lobby_link: Option<LobbyLink>,
     pub client: Option<Client>,
     pub id: ConnId,
 }
@@ -1,10 +1,106 @@
+use common::resources::{ResourceType, Resources};
+
+use crate::game::kit::StartingKit;
+
 // Server constants
 pub const SERVER_TICK: u64 = 30;
 
+// Admin commands are allowed only to the clients logged in with the admin
+// token of the server, see ADMIN_TOKEN_VAR
+pub const ADMIN_AUDIT_LOG: &str = "admin_audit.log";
+
+// Starting kit constants
+
+// Kit given to every new castle of a lobby
+pub const DEFAULT_KIT: &str = "standard";
+
+// Units are Knight, Mage, Dragon, Ship. The debug kits replace the old name
+// based loadouts, they can only be granted by admins.
+pub const STARTING_KITS: &[StartingKit] = &[
+    StartingKit {
+        name: "standard",
+        resources: Resources::from_pairs(&[
+            (ResourceType::Wood, 10),
+            (ResourceType::Stone, 10),
+            (ResourceType::Food, 50),
+            (ResourceType::Gold, 100),
+        ]),
+        units: [5, 0, 0, 0],
+    },
+    StartingKit {
+        name: "debug_small",
+        resources: Resources::from_pairs(&[
+            (ResourceType::Wood, 100),
+            (ResourceType::Stone, 100),
+            (ResourceType::Food, 500),
+            (ResourceType::Gold, 100),
+            (ResourceType::Iron, 100),
+        ]),
+        units: [100, 0, 0, 0],
+    },
+    StartingKit {
+        name: "debug_large",
+        resources: Resources::from_pairs(&[
+            (ResourceType::Wood, 1000),
+            (ResourceType::Stone, 1000),
+            (ResourceType::Food, 5000),
+            (ResourceType::Gold, 1000),
+            (ResourceType::Iron, 1000),
+        ]),
+        units: [1000, 1000, 1000, 0],
+    },
+];
+
 // Lobby constants
 
 pub const GAME_TICK: u64 = 1000;
 pub const LOBBY_POOL_LEN: usize = 4;
+// Game ticks to wait for a route before giving up
+pub const PATHFINDING_TIMEOUT: u32 = 10;
+
+// Courtyard constants
+
+// Jobs progressing at the same time in a castle build queue
+pub const BUILD_SLOTS: usize = 1;
+pub const BUILD_QUEUE_LEN: usize = 5;
+pub const TRAINING_QUEUE_LEN: usize = 5;
+pub const RESEARCH_QUEUE_LEN: usize = 3;
+// Share of the resources spent on a facility given back when demolished
+pub const DEMOLISH_REFUND_PERCENT: u32 = 50;
+
+// Storage constants
+
+// Storage added by each warehouse level, as a percentage of the base storage
+pub const WAREHOUSE_STORAGE_PERCENT: u32 = 50;
+// Ticks between two reports of the resources wasted for lack of storage
+pub const WASTE_REPORT_TICKS: u32 = 30;
+
+// Food constants
+
+pub const FARM_FOOD_PER_LV: u32 = 3;
+// Units eat tenths of food each tick, like peasants
+pub const PEASANT_FOOD_TENTHS: u32 = 1;
+// Share of peasants and garrison lost on each tick without enough food
+pub const STARVATION_PERCENT: u32 = 10;
+
+// Share of every unit type deserting on each tick the upkeep is not paid
+pub const UPKEEP_DESERTION_PERCENT: u32 = 5;
+
+// Battle constants
+
+// Distance in tiles at which the defender is warned of an incoming attack
+pub const ATTACK_WARNING_DISTANCE: usize = 32;
+
+// A defeated army retreats instead of being destroyed if its strength is at
+// least this percentage of the winner's
+pub const ROUT_THRESHOLD_PERCENT: u32 = 50;
+
+// Ticks a retreating army can't be caught in another field battle
+pub const DISENGAGE_TICKS: u32 = 10;
+
+// Share of each resource the attackers try to carry off from a castle,
+// halved if they had to retreat
+pub const PLUNDER_PERCENT: u32 = 50;
 
 // Map initialization constants
 
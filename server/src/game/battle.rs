@@ -0,0 +1,69 @@
+use common::{
+    packets::BattleOutcome,
+    units::{UnitGroup, UnitType},
+};
+
+use crate::r#const::ROUT_THRESHOLD_PERCENT;
+
+pub struct BattleResult {
+    // Indexed like the fighting groups
+    pub outcomes: [BattleOutcome; 2],
+    pub losses: [UnitGroup; 2],
+}
+
+// The stronger group wins, losing up to half of its units in a close fight.
+// The loser retreats with half of its units if it was strong enough, otherwise it's wiped out.
+// Returns None if neither group can fight.
+// The strength bonus percents are indexed like the groups, then by unit type.
+pub fn fight(
+    a: &mut UnitGroup,
+    b: &mut UnitGroup,
+    bonus: [[u32; UnitType::COUNT]; 2],
+) -> Option<BattleResult> {
+    let a_str = a.get_boosted_strength(&bonus[0]);
+    let b_str = b.get_boosted_strength(&bonus[1]);
+
+    if a_str == 0 && b_str == 0 {
+        return None;
+    }
+
+    let result = if a_str == b_str {
+        BattleResult {
+            outcomes: [BattleOutcome::Retreat, BattleOutcome::Retreat],
+            losses: [take_losses(a, 1, 2), take_losses(b, 1, 2)],
+        }
+    } else if a_str > b_str {
+        let (loser_outcome, loser_losses) = defeat(b, b_str, a_str);
+        BattleResult {
+            outcomes: [BattleOutcome::Victory, loser_outcome],
+            losses: [take_losses(a, b_str, a_str.saturating_mul(2)), loser_losses],
+        }
+    } else {
+        let (loser_outcome, loser_losses) = defeat(a, a_str, b_str);
+        BattleResult {
+            outcomes: [loser_outcome, BattleOutcome::Victory],
+            losses: [loser_losses, take_losses(b, a_str, b_str.saturating_mul(2))],
+        }
+    };
+
+    Some(result)
+}
+
+fn defeat(loser: &mut UnitGroup, loser_str: u32, winner_str: u32) -> (BattleOutcome, UnitGroup) {
+    if loser_str as u64 * 100 >= winner_str as u64 * ROUT_THRESHOLD_PERCENT as u64 {
+        (BattleOutcome::Retreat, take_losses(loser, 1, 2))
+    } else {
+        (BattleOutcome::Defeat, take_losses(loser, 1, 1))
+    }
+}
+
+// Removes num/den of every unit type from the group and returns what was removed
+fn take_losses(group: &mut UnitGroup, num: u32, den: u32) -> UnitGroup {
+    let mut losses = UnitGroup::new();
+    for (unit, count) in group.iter_present() {
+        let lost = (count as u64 * num as u64 / den as u64) as u32;
+        losses.add_single_type(unit, lost);
+    }
+    group.subtract_unchecked(&losses);
+    losses
+}
@@ -1,13 +1,27 @@
 use std::collections::HashMap;
 
 use common::{
-    GameCoord, Resources,
-    courtyard::{Facility, FacilityType},
+    GameCoord, GameId, all_resources,
+    r#const::CASTLE_MAX_LV,
+    courtyard::{BuildJob, Facility, FacilityType, TrainingJob, castle_upgrade_cost},
     game_objs::{CastleE, OwnedCastleE},
+    packets::{EventE, Rejection},
+    research::{self, TechType},
+    resources::{ResourceType, Resources},
     units::{UnitGroup, UnitType},
 };
 
-use crate::game::courtyard::{Courtyard, CourtyardEvent};
+use crate::{
+    r#const::{
+        DEMOLISH_REFUND_PERCENT, PEASANT_FOOD_TENTHS, STARVATION_PERCENT, UPKEEP_DESERTION_PERCENT,
+        WAREHOUSE_STORAGE_PERCENT, WASTE_REPORT_TICKS,
+    },
+    game::{
+        courtyard::{Courtyard, CourtyardEvent},
+        kit::StartingKit,
+        research::Research,
+    },
+};
 
 pub struct Castle {
     name: String,
@@ -16,82 +30,422 @@ pub struct Castle {
     units: UnitGroup,
     resources: Resources,
     courtyard: Courtyard,
+    research: Research,
+    // Units of other castles in the garrison, by owner castle
+    reinforcements: HashMap<GameId, UnitGroup>,
+    // Food produced minus food eaten on the last tick
+    food_balance: i32,
+    // Paid on the last tick, units away included
+    upkeep: Resources,
+    upkeep_unpaid: bool,
+    starving: bool,
+    lv: u32,
+    // Resources that did not fit in the storage, since the last report
+    wasted: Resources,
+    waste_report_cnt: u32,
 }
 
 impl Castle {
-    pub fn new(name: String, pos: GameCoord) -> Self {
-        let mut units = UnitGroup::new();
-        let mut resources = Resources::new(10, 10);
-
-        if name == "gabbiano" {
-            resources.saturating_add(&Resources::new(100, 100));
-            units.add_single_type(UnitType::Knight, 100);
-        } else if name == "pellicano" {
-            resources.saturating_add(&Resources::new(1000, 1000));
-            units.add_single_type(UnitType::Knight, 1000);
-            units.add_single_type(UnitType::Mage, 1000);
-            units.add_single_type(UnitType::Dragon, 1000);
-        } else {
-            units.add_single_type(UnitType::Knight, 5);
-        }
-
-        Self {
+    pub fn new(name: String, pos: GameCoord, kit: &StartingKit) -> Self {
+        let mut castle = Self {
             name,
             pos,
             is_alive: true,
-            units,
-            resources,
+            units: kit.get_units(),
+            resources: Resources::new(),
             courtyard: Courtyard::new(),
+            research: Research::new(),
+            reinforcements: HashMap::new(),
+            food_balance: 0,
+            upkeep: Resources::new(),
+            upkeep_unpaid: false,
+            starving: false,
+            lv: 1,
+            wasted: Resources::new(),
+            waste_report_cnt: 0,
+        };
+        castle.store(&kit.resources);
+        castle
+    }
+
+    pub fn get_storage_cap(&self) -> Resources {
+        let warehouse_lv = self.courtyard.get_warehouse_lv();
+        let mut cap = Resources::new();
+        for resource in all_resources!() {
+            let base = resource.base_storage();
+            let bonus = base * WAREHOUSE_STORAGE_PERCENT / 100 * warehouse_lv;
+            cap.add_single_type(resource, base * self.lv + bonus);
         }
+        cap
+    }
+
+    // Every resource entering the castle goes through here, what doesn't fit
+    // in the storage is wasted
+    fn store(&mut self, resources: &Resources) {
+        self.resources.saturating_add(resources);
+        let overflow = self.resources.cap(&self.get_storage_cap());
+        self.wasted.saturating_add(&overflow);
     }
 
     pub fn is_alive(&self) -> bool {
         self.is_alive
     }
 
-    pub fn new_facility(&mut self, r#type: FacilityType, pos: GameCoord) -> bool {
-        let cost = r#type.base_cost();
+    pub fn new_facility(&mut self, r#type: FacilityType, pos: GameCoord) -> Result<(), Rejection> {
+        if let Some(tech) = research::required_tech(r#type)
+            && !self.get_researched().contains(&tech)
+        {
+            return Err(Rejection::FacilityLocked);
+        }
+        let cost = self.discounted(r#type, r#type.base_cost());
+        if !self.resources.contains(&cost) {
+            return Err(Rejection::NotEnoughResources);
+        }
+        self.courtyard.new_facility(r#type, pos, self.lv)?;
+        self.resources.saturating_sub(&cost);
+        Ok(())
+    }
+
+    pub fn upgrade_facility(&mut self, id: u8) -> Result<(), Rejection> {
+        let facility = self
+            .courtyard
+            .get_facility(id)
+            .ok_or(Rejection::InvalidTarget)?;
+        let cost = self.discounted(facility.r#type, facility.upgrade_cost());
         if !self.resources.contains(&cost) {
-            return false;
+            return Err(Rejection::NotEnoughResources);
+        }
+        self.courtyard.upgrade_facility(id)?;
+        self.resources.saturating_sub(&cost);
+        Ok(())
+    }
+
+    // The refund assumes every level was paid with the current discounts.
+    // Training that only the facility could carry on is refunded in full.
+    pub fn demolish_facility(&mut self, id: u8) -> Result<(), Rejection> {
+        let r#type = self
+            .courtyard
+            .get_facility(id)
+            .ok_or(Rejection::InvalidTarget)?
+            .r#type;
+        let spent = self.courtyard.demolish_facility(id)?;
+        let spent = self.discounted(r#type, spent);
+        self.store(&spent.percent(DEMOLISH_REFUND_PERCENT));
+        for (unit, remaining) in self.courtyard.cancel_stalled_training(r#type) {
+            self.store(&unit.training_cost().times(remaining));
+        }
+        Ok(())
+    }
+
+    // Facility costs lowered by the techs researched
+    fn discounted(&self, r#type: FacilityType, cost: Resources) -> Resources {
+        cost.percent(research::facility_cost_percent(
+            self.get_researched(),
+            r#type,
+        ))
+    }
+
+    pub fn research(&mut self, tech: TechType) -> Result<(), Rejection> {
+        let cost = tech.cost();
+        if !self.resources.contains(&cost) {
+            return Err(Rejection::NotEnoughResources);
+        }
+        self.research.queue_tech(tech)?;
+        self.resources.saturating_sub(&cost);
+        Ok(())
+    }
+
+    pub fn get_researched(&self) -> &[TechType] {
+        self.research.get_researched()
+    }
+
+    pub fn relocate_facility(&mut self, id: u8, pos: GameCoord) -> Result<(), Rejection> {
+        self.courtyard.relocate_facility(id, pos, self.lv)
+    }
+
+    // The courtyard grows and more facilities can be built right away
+    pub fn upgrade(&mut self) -> Result<(), Rejection> {
+        if self.lv >= CASTLE_MAX_LV {
+            return Err(Rejection::MaxLevelReached);
+        }
+        if !self
+            .resources
+            .subtract_if_enough(&castle_upgrade_cost(self.lv))
+        {
+            return Err(Rejection::NotEnoughResources);
         }
-        if !self.courtyard.new_facility(r#type, pos) {
-            return false;
+        self.lv += 1;
+        Ok(())
+    }
+
+    pub fn assign_workers(&mut self, id: u8, workers: u32) -> Result<(), Rejection> {
+        self.courtyard.assign_workers(id, workers)
+    }
+
+    pub fn train_units(&mut self, unit: UnitType, count: u32) -> Result<(), Rejection> {
+        let cost = unit.training_cost().times(count);
+        if !self.resources.contains(&cost) {
+            return Err(Rejection::NotEnoughResources);
         }
+        self.courtyard.train_units(unit, count)?;
         self.resources.saturating_sub(&cost);
-        true
+        Ok(())
+    }
+
+    // Units not trained yet are refunded in full
+    pub fn cancel_training(&mut self, job_id: u32) -> Result<(), Rejection> {
+        let (unit, remaining) = self.courtyard.cancel_training(job_id)?;
+        self.store(&unit.training_cost().times(remaining));
+        Ok(())
+    }
+
+    pub fn add_kit(&mut self, kit: &StartingKit) {
+        self.units.saturating_add(&kit.get_units());
+        self.store(&kit.resources);
     }
 
     pub fn add_units(&mut self, units: &UnitGroup) {
         self.units.saturating_add(units);
     }
 
+    // Reinforcements are kept apart from the garrison, they only defend the castle
+    pub fn add_reinforcements(&mut self, owner_id: GameId, units: &UnitGroup) {
+        self.reinforcements
+            .entry(owner_id)
+            .or_insert_with(UnitGroup::new)
+            .saturating_add(units);
+    }
+
+    pub fn take_reinforcements(&mut self, owner_id: GameId) -> Option<UnitGroup> {
+        self.reinforcements
+            .remove(&owner_id)
+            .filter(|units| !units.is_empty())
+    }
+
+    // The owner pays the upkeep of its reinforcements and feeds them,
+    // so they desert when it can't
+    pub fn desert_reinforcements(&mut self, owner_id: GameId, starving: bool) -> UnitGroup {
+        let Some(units) = self.reinforcements.get_mut(&owner_id) else {
+            return UnitGroup::new();
+        };
+        let deserters = if starving {
+            starve(units, STARVATION_PERCENT)
+        } else {
+            desert(units, UPKEEP_DESERTION_PERCENT)
+        };
+        units.subtract_unchecked(&deserters);
+        if units.is_empty() {
+            self.reinforcements.remove(&owner_id);
+        }
+        deserters
+    }
+
+    pub fn get_reinforcements(&self, owner_id: GameId) -> Option<&UnitGroup> {
+        self.reinforcements.get(&owner_id)
+    }
+
+    // Reinforcements in the garrison, by owner castle
+    pub fn iter_reinforcements(&self) -> impl Iterator<Item = (&GameId, &UnitGroup)> {
+        self.reinforcements.iter()
+    }
+
+    pub fn add_resources(&mut self, resources: &Resources) {
+        self.store(resources);
+    }
+
+    // Takes percent of every resource, scaled down evenly to what can be carried
+    pub fn plunder(&mut self, percent: u32, capacity: u32) -> Resources {
+        let mut loot = self.resources.percent(percent);
+        let total = loot.total();
+        if total > capacity {
+            for amount in loot.quantities.iter_mut() {
+                *amount = (*amount as u64 * capacity as u64 / total as u64) as u32;
+            }
+        }
+        self.resources.saturating_sub(&loot);
+        loot
+    }
+
+    pub fn subtract_resources_if_enough(&mut self, resources: &Resources) -> bool {
+        self.resources.subtract_if_enough(resources)
+    }
+
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
     pub fn subtract_units_if_enough(&mut self, units: &UnitGroup) -> bool {
         self.units.subtract_if_enough(units)
     }
 
-    pub fn get_strength(&self) -> u32 {
-        self.units.get_strength()
+    // The garrison together with the reinforcements
+    pub fn get_defenders(&self) -> UnitGroup {
+        let mut defenders = self.units.clone();
+        for units in self.reinforcements.values() {
+            defenders.saturating_add(units);
+        }
+        defenders
+    }
+
+    // The losses of a defence are shared by the garrison and the reinforcements,
+    // in proportion to the units each one brought
+    pub fn take_defence_losses(&mut self, losses: &UnitGroup) {
+        let defenders = self.get_defenders();
+        for (unit, lost) in losses.iter_present() {
+            let i = unit.as_index();
+            let total = defenders.quantities[i] as u64;
+            let mut left = lost;
+            for units in self.reinforcements.values_mut() {
+                let share = (lost as u64 * units.quantities[i] as u64 / total) as u32;
+                let share = share.min(left);
+                units.subtract_single_type(unit, share);
+                left -= share;
+            }
+            // The garrison takes the rest, the reinforcements cover what it lacks
+            let share = left.min(self.units.quantities[i]);
+            self.units.subtract_single_type(unit, share);
+            left -= share;
+            for units in self.reinforcements.values_mut() {
+                let share = left.min(units.quantities[i]);
+                units.subtract_single_type(unit, share);
+                left -= share;
+            }
+        }
+        self.reinforcements.retain(|_, units| !units.is_empty());
     }
 
     pub fn get_pos(&self) -> GameCoord {
         self.pos
     }
 
+    // The reinforcements fall with the castle
     pub fn kill(&mut self) {
         self.is_alive = false;
+        self.reinforcements.clear();
     }
 
-    pub fn update(&mut self) {
-        for event in self.courtyard.update().iter() {
+    // Units away are the deployed ones and the ones in other garrisons
+    pub fn update(&mut self, units_away: &UnitGroup) -> Vec<EventE> {
+        let mut events = Vec::new();
+        let mut food_prod = 0;
+        // Peasants only grow while the farms produce more than what is eaten
+        let fed = self.food_balance > 0;
+        for event in self.courtyard.update(fed).iter() {
             match event {
                 CourtyardEvent::ResourceProduction(resources) => {
-                    self.resources.saturating_add(resources);
+                    let mut resources = resources.clone();
+                    for (resource, amount) in resources.quantities.iter_mut().enumerate() {
+                        let bonus = research::production_bonus_percent(
+                            self.research.get_researched(),
+                            ResourceType::from_index(resource),
+                        );
+                        *amount += *amount * bonus / 100;
+                    }
+                    self.store(&resources);
+                    food_prod += resources.get(ResourceType::Food);
                 }
                 CourtyardEvent::UnitsProduction(units) => {
                     self.units.saturating_add(units);
                 }
+                CourtyardEvent::UnitsTrained(unit, count) => {
+                    events.push(EventE::UnitsTrained {
+                        unit: *unit,
+                        count: *count,
+                    });
+                }
+                CourtyardEvent::FacilityBuilt(r#type, lv) => {
+                    events.push(EventE::FacilityBuilt {
+                        r#type: *r#type,
+                        lv: *lv,
+                    });
+                }
             }
         }
+        if let Some(tech) = self.research.update() {
+            events.push(EventE::TechResearched { tech });
+        }
+        events.extend(self.eat(food_prod, units_away));
+        events.extend(self.pay_upkeep(units_away));
+        events.extend(self.report_waste());
+        events
+    }
+
+    fn report_waste(&mut self) -> Option<EventE> {
+        self.waste_report_cnt += 1;
+        if self.waste_report_cnt < WASTE_REPORT_TICKS {
+            return None;
+        }
+        self.waste_report_cnt = 0;
+        if self.wasted.is_empty() {
+            return None;
+        }
+        let wasted = std::mem::take(&mut self.wasted);
+        Some(EventE::ResourcesWasted { wasted })
+    }
+
+    // What can't be paid is lost anyway, and part of the garrison deserts.
+    // Reinforcements from other castles are paid by their owner, the upkeep
+    // of the units away is paid here and their deserters are added by the game.
+    fn pay_upkeep(&mut self, units_away: &UnitGroup) -> Option<EventE> {
+        let mut units = self.units.clone();
+        units.saturating_add(units_away);
+        self.upkeep = units.get_upkeep();
+
+        self.upkeep_unpaid = !self.resources.subtract_if_enough(&self.upkeep);
+        if !self.upkeep_unpaid {
+            return None;
+        }
+        self.resources.saturating_sub(&self.upkeep);
+
+        let deserters = desert(&self.units, UPKEEP_DESERTION_PERCENT);
+        self.units.subtract_unchecked(&deserters);
+        Some(EventE::UpkeepUnpaid { deserters })
+    }
+
+    pub fn is_upkeep_unpaid(&self) -> bool {
+        self.upkeep_unpaid
+    }
+
+    // Peasants and all the units eat, the ones away included. When the food is
+    // not enough part of the garrison deserts, the deserters away are added by the game.
+    fn eat(&mut self, food_prod: u32, units_away: &UnitGroup) -> Option<EventE> {
+        let mut units = self.units.clone();
+        units.saturating_add(units_away);
+        let consumption = self.get_food_consumption(&units);
+        self.food_balance = food_prod as i32 - consumption as i32;
+
+        let food = self.resources.get_mut(ResourceType::Food);
+        self.starving = *food < consumption;
+        if !self.starving {
+            *food -= consumption;
+            return None;
+        }
+        *food = 0;
+
+        let peasants = self.courtyard.starve_peasants(STARVATION_PERCENT);
+        let deserters = starve(&self.units, STARVATION_PERCENT);
+        self.units.subtract_unchecked(&deserters);
+
+        Some(EventE::Starvation {
+            peasants,
+            deserters,
+        })
+    }
+
+    pub fn is_starving(&self) -> bool {
+        self.starving
+    }
+
+    fn get_food_consumption(&self, units: &UnitGroup) -> u32 {
+        let units_tenths: u32 = units
+            .iter_present()
+            .map(|(unit, count)| count.saturating_mul(unit.food_tenths()))
+            .fold(0, u32::saturating_add);
+        let peasants_tenths = self
+            .courtyard
+            .get_population()
+            .saturating_mul(PEASANT_FOOD_TENTHS);
+        units_tenths.saturating_add(peasants_tenths).div_ceil(10)
     }
 
     pub fn export(&self) -> CastleE {
@@ -99,6 +453,7 @@ impl Castle {
             name: self.name.clone(),
             pos: self.pos,
             alive: self.is_alive,
+            lv: self.lv,
         }
     }
 
@@ -106,13 +461,56 @@ impl Castle {
         self.courtyard.export()
     }
 
+    pub fn export_build_queue(&self) -> Vec<BuildJob> {
+        self.courtyard.export_build_queue()
+    }
+
+    pub fn export_training_queue(&self) -> Vec<TrainingJob> {
+        self.courtyard.export_training_queue()
+    }
+
+    pub fn get_peasants(&self) -> u32 {
+        self.courtyard.get_peasants()
+    }
+
     pub fn export_owned(&self) -> OwnedCastleE {
         OwnedCastleE {
             alive: self.is_alive,
+            lv: self.lv,
             name: self.name.clone(),
             pos: self.pos,
             units: self.units.clone(),
             resources: self.resources.clone(),
+            lent_units: HashMap::new(),
+            food_balance: self.food_balance,
+            storage_cap: self.get_storage_cap(),
+            upkeep: self.upkeep.clone(),
+            researched: self.get_researched().to_vec(),
+            research_queue: self.research.export_queue(),
+        }
+    }
+}
+
+// Percent of every unit type with an upkeep, at least one of each
+pub fn desert(units: &UnitGroup, percent: u32) -> UnitGroup {
+    let mut deserters = UnitGroup::new();
+    for (unit, count) in units.iter_present() {
+        if !unit.upkeep_tenths().is_empty() {
+            let count = (count as u64 * percent as u64 / 100) as u32;
+            deserters.add_single_type(unit, count.max(1));
+        }
+    }
+    deserters
+}
+
+// Percent of every unit type that eats, at least one of each
+pub fn starve(units: &UnitGroup, percent: u32) -> UnitGroup {
+    let mut deserters = UnitGroup::new();
+    for (unit, count) in units.iter_present() {
+        if unit.food_tenths() > 0 {
+            let count = (count as u64 * percent as u64 / 100) as u32;
+            deserters.add_single_type(unit, count.max(1));
         }
     }
+    deserters
 }
@@ -1,15 +1,21 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 
 use common::{
-    GameCoord, Resources,
+    GameCoord,
     r#const::{COURTYARD_COLS, COURTYARD_ROWS},
-    courtyard::{Facility, FacilityType},
+    courtyard::{BuildJob, BuildKind, Facility, FacilityType, TrainingJob, courtyard_area},
+    packets::Rejection,
+    resources::{ResourceType, Resources},
     units::{UnitGroup, UnitType},
 };
 
+use crate::r#const::{BUILD_QUEUE_LEN, BUILD_SLOTS, FARM_FOOD_PER_LV, TRAINING_QUEUE_LEN};
+
 pub enum CourtyardEvent {
     ResourceProduction(Resources),
     UnitsProduction(UnitGroup),
+    FacilityBuilt(FacilityType, u32),
+    UnitsTrained(UnitType, u32),
 }
 
 pub struct Courtyard {
@@ -18,6 +24,10 @@ pub struct Courtyard {
     occupied: [[Option<u8>; COURTYARD_COLS]; COURTYARD_ROWS],
     owned_cnt: [u8; FacilityType::COUNT],
     id_cnt: u8,
+    build_queue: VecDeque<BuildJob>,
+    build_slots: usize,
+    training_queue: Vec<TrainingJob>,
+    training_id_cnt: u32,
 }
 
 impl Courtyard {
@@ -28,34 +38,52 @@ impl Courtyard {
             occupied: [[None; COURTYARD_COLS]; COURTYARD_ROWS],
             owned_cnt: [0; FacilityType::COUNT],
             id_cnt: 0,
+            build_queue: VecDeque::new(),
+            build_slots: BUILD_SLOTS,
+            training_queue: Vec::new(),
+            training_id_cnt: 0,
         }
     }
 
-    pub fn update(&mut self) -> Vec<CourtyardEvent> {
+    pub fn update(&mut self, fed: bool) -> Vec<CourtyardEvent> {
         let mut events = Vec::new();
-        let mut resource_prod = Resources::new(0, 0);
+        let mut resource_prod = Resources::new();
         let mut units_prod = UnitGroup::new();
         let mut peasants_prod = 0;
 
         for facility in self.facilities.values() {
             match facility.r#type {
-                FacilityType::FarmPlot => peasants_prod += facility.lv,
-                FacilityType::Sawmill => resource_prod.wood += facility.lv * 10,
-                FacilityType::Mines => resource_prod.stone += facility.lv * 10,
-                FacilityType::Barracks => {
-                    let knights = facility.lv * 5;
-                    if self.peasants >= knights {
-                        self.peasants -= knights;
-                        units_prod.add_single_type(UnitType::Knight, knights);
-                    }
+                FacilityType::FarmPlot => {
+                    peasants_prod += facility.lv;
+                    resource_prod.add_single_type(
+                        ResourceType::Food,
+                        facility.staffed(facility.lv * FARM_FOOD_PER_LV),
+                    );
                 }
-                FacilityType::Shipyard => {
-                    units_prod.add_single_type(UnitType::Ship, facility.lv);
+                FacilityType::Sawmill => resource_prod
+                    .add_single_type(ResourceType::Wood, facility.staffed(facility.lv * 10)),
+                FacilityType::Mines => {
+                    resource_prod
+                        .add_single_type(ResourceType::Stone, facility.staffed(facility.lv * 10));
+                    resource_prod
+                        .add_single_type(ResourceType::Iron, facility.staffed(facility.lv * 2));
+                    resource_prod
+                        .add_single_type(ResourceType::Gold, facility.staffed(facility.lv));
                 }
+                // Units are trained on order, see progress_training
+                FacilityType::Barracks
+                | FacilityType::MageTower
+                | FacilityType::DragonRoost
+                | FacilityType::Shipyard => {}
+                FacilityType::Warehouse => {}
             }
         }
 
-        if resource_prod.wood > 0 || resource_prod.stone > 0 {
+        for (unit, count) in self.progress_training(&mut units_prod) {
+            events.push(CourtyardEvent::UnitsTrained(unit, count));
+        }
+
+        if !resource_prod.is_empty() {
             events.push(CourtyardEvent::ResourceProduction(resource_prod));
         }
 
@@ -63,30 +91,254 @@ impl Courtyard {
             events.push(CourtyardEvent::UnitsProduction(units_prod));
         }
 
-        if peasants_prod > 0 {
+        if fed && peasants_prod > 0 {
             self.peasants = self.peasants.saturating_add(peasants_prod);
         }
 
+        for id in self.progress_builds() {
+            if let Some(facility) = self.facilities.get_mut(&id) {
+                facility.lv += 1;
+                events.push(CourtyardEvent::FacilityBuilt(facility.r#type, facility.lv));
+            }
+        }
+
         events
     }
 
-    pub fn new_facility(&mut self, r#type: FacilityType, pos: GameCoord) -> bool {
+    // Only the first job of each facility type progresses, faster with the
+    // facility level. Returns the jobs completed.
+    fn progress_training(&mut self, units_prod: &mut UnitGroup) -> Vec<(UnitType, u32)> {
+        let mut busy = [false; FacilityType::COUNT];
+
+        for job in self.training_queue.iter_mut() {
+            let Some(facility_type) = job.unit.trained_at() else {
+                continue;
+            };
+            if busy[facility_type.as_index()] {
+                continue;
+            }
+            busy[facility_type.as_index()] = true;
+
+            let lv = self
+                .facilities
+                .values()
+                .filter(|facility| facility.r#type == facility_type)
+                .map(|facility| facility.lv)
+                .max()
+                .unwrap_or(0);
+            job.ticks_left = job.ticks_left.saturating_sub(lv);
+            if lv > 0 && job.ticks_left == 0 {
+                job.trained += 1;
+                units_prod.add_single_type(job.unit, 1);
+                job.ticks_left = job.unit.training_ticks();
+            }
+        }
+
+        let mut done = Vec::new();
+        self.training_queue.retain(|job| {
+            if job.remaining() == 0 {
+                done.push((job.unit, job.count));
+            }
+            job.remaining() > 0
+        });
+        done
+    }
+
+    // Returns the facilities whose job is done
+    fn progress_builds(&mut self) -> Vec<u8> {
+        let mut done = Vec::new();
+        for job in self.build_queue.iter_mut().take(self.build_slots) {
+            job.ticks_left = job.ticks_left.saturating_sub(1);
+            if job.ticks_left == 0 {
+                done.push(job.facility_id);
+            }
+        }
+        self.build_queue.retain(|job| job.ticks_left > 0);
+        done
+    }
+
+    pub fn new_facility(
+        &mut self,
+        r#type: FacilityType,
+        pos: GameCoord,
+        castle_lv: u32,
+    ) -> Result<(), Rejection> {
         let type_idx = r#type.as_index();
-        let max_count = r#type.max_count();
 
-        if self.owned_cnt[type_idx] >= max_count {
-            return false;
+        if castle_lv < r#type.unlock_lv() {
+            return Err(Rejection::FacilityLocked);
+        }
+        if self.owned_cnt[type_idx] >= r#type.max_count(castle_lv) {
+            return Err(Rejection::MaxFacilitiesReached);
         }
-        if !self.is_position_valid(pos, r#type.size()) {
-            return false;
+        if self.build_queue.len() >= BUILD_QUEUE_LEN {
+            return Err(Rejection::BuildQueueFull);
         }
+        self.check_position(pos, r#type.size(), castle_lv)?;
 
         let id = self.new_id();
         let facility = Facility::new(r#type, pos);
         self.facilities.insert(id, facility);
         self.mark_occupied(id, pos, r#type.size());
+        self.owned_cnt[type_idx] += 1;
+        self.build_queue.push_back(BuildJob::new(
+            id,
+            BuildKind::Construction,
+            r#type.build_ticks(1),
+        ));
+
+        Ok(())
+    }
+
+    pub fn get_facility(&self, id: u8) -> Option<&Facility> {
+        self.facilities.get(&id)
+    }
+
+    // The level goes up once the job is done, see progress_builds
+    pub fn upgrade_facility(&mut self, id: u8) -> Result<(), Rejection> {
+        let facility = self.facilities.get(&id).ok_or(Rejection::InvalidTarget)?;
+        if self.build_queue.iter().any(|job| job.facility_id == id) {
+            return Err(Rejection::AlreadyBuilding);
+        }
+        if facility.is_max_lv() {
+            return Err(Rejection::MaxLevelReached);
+        }
+        if self.build_queue.len() >= BUILD_QUEUE_LEN {
+            return Err(Rejection::BuildQueueFull);
+        }
+
+        let ticks = facility.r#type.build_ticks(facility.lv + 1);
+        self.build_queue
+            .push_back(BuildJob::new(id, BuildKind::Upgrade, ticks));
+        Ok(())
+    }
+
+    // Returns the resources spent on the facility, queued upgrade included
+    pub fn demolish_facility(&mut self, id: u8) -> Result<Resources, Rejection> {
+        let facility = self
+            .facilities
+            .remove(&id)
+            .ok_or(Rejection::InvalidTarget)?;
+        let r#type = facility.r#type;
+
+        let mut paid_lv = facility.lv.max(1);
+        if let Some(job) = self.build_queue.iter().find(|job| job.facility_id == id)
+            && job.kind == BuildKind::Upgrade
+        {
+            paid_lv += 1;
+        }
+        self.build_queue.retain(|job| job.facility_id != id);
+
+        self.clear_occupied(facility.pos, r#type.size());
+        self.owned_cnt[r#type.as_index()] -= 1;
+        self.peasants = self.peasants.saturating_add(facility.workers);
+
+        Ok(r#type.total_cost(paid_lv))
+    }
+
+    pub fn relocate_facility(
+        &mut self,
+        id: u8,
+        pos: GameCoord,
+        castle_lv: u32,
+    ) -> Result<(), Rejection> {
+        let facility = self.facilities.get(&id).ok_or(Rejection::InvalidTarget)?;
+        let (old_pos, size) = (facility.pos, facility.r#type.size());
+
+        self.clear_occupied(old_pos, size);
+        if let Err(err) = self.check_position(pos, size, castle_lv) {
+            self.mark_occupied(id, old_pos, size);
+            return Err(err);
+        }
+        self.mark_occupied(id, pos, size);
+        if let Some(facility) = self.facilities.get_mut(&id) {
+            facility.pos = pos;
+        }
+        Ok(())
+    }
+
+    // Moves peasants between the idle ones and the facility
+    pub fn assign_workers(&mut self, id: u8, workers: u32) -> Result<(), Rejection> {
+        let facility = self
+            .facilities
+            .get_mut(&id)
+            .ok_or(Rejection::InvalidTarget)?;
+        if workers > facility.max_workers() {
+            return Err(Rejection::MaxWorkersReached);
+        }
+        if workers > facility.workers && workers - facility.workers > self.peasants {
+            return Err(Rejection::NotEnoughPeasants);
+        }
 
-        true
+        self.peasants = self.peasants + facility.workers - workers;
+        facility.workers = workers;
+        Ok(())
+    }
+
+    // Resources are paid by the castle, peasants here
+    pub fn train_units(&mut self, unit: UnitType, count: u32) -> Result<(), Rejection> {
+        let facility_type = unit.trained_at().ok_or(Rejection::InvalidTarget)?;
+        if count == 0 {
+            return Err(Rejection::NotEnoughUnits);
+        }
+        if !self
+            .facilities
+            .values()
+            .any(|facility| facility.r#type == facility_type && facility.is_built())
+        {
+            return Err(Rejection::MissingFacility);
+        }
+        if self.training_queue.len() >= TRAINING_QUEUE_LEN {
+            return Err(Rejection::TrainingQueueFull);
+        }
+        let peasants = unit.training_peasants().saturating_mul(count);
+        if self.peasants < peasants {
+            return Err(Rejection::NotEnoughPeasants);
+        }
+
+        self.peasants -= peasants;
+        self.training_id_cnt += 1;
+        self.training_queue
+            .push(TrainingJob::new(self.training_id_cnt, unit, count));
+        Ok(())
+    }
+
+    // Gives back the peasants, returns the units left to train
+    pub fn cancel_training(&mut self, id: u32) -> Result<(UnitType, u32), Rejection> {
+        let idx = self
+            .training_queue
+            .iter()
+            .position(|job| job.id == id)
+            .ok_or(Rejection::InvalidTarget)?;
+        let job = self.training_queue.remove(idx);
+
+        let remaining = job.remaining();
+        self.peasants = self
+            .peasants
+            .saturating_add(job.unit.training_peasants().saturating_mul(remaining));
+        Ok((job.unit, remaining))
+    }
+
+    // Training jobs left without a facility of their type would never
+    // progress, so they are cancelled. Returns the units left to train.
+    pub fn cancel_stalled_training(&mut self, r#type: FacilityType) -> Vec<(UnitType, u32)> {
+        if self
+            .facilities
+            .values()
+            .any(|facility| facility.r#type == r#type)
+        {
+            return Vec::new();
+        }
+        let stalled: Vec<u32> = self
+            .training_queue
+            .iter()
+            .filter(|job| job.unit.trained_at() == Some(r#type))
+            .map(|job| job.id)
+            .collect();
+        stalled
+            .into_iter()
+            .filter_map(|id| self.cancel_training(id).ok())
+            .collect()
     }
 
     fn new_id(&mut self) -> u8 {
@@ -94,19 +346,30 @@ impl Courtyard {
         self.id_cnt
     }
 
-    fn is_position_valid(&self, pos: GameCoord, size: GameCoord) -> bool {
-        if pos.x + size.x > COURTYARD_COLS || pos.y + size.y > COURTYARD_ROWS {
-            return false;
+    // The facility must fit in the area unlocked by the castle level
+    fn check_position(
+        &self,
+        pos: GameCoord,
+        size: GameCoord,
+        castle_lv: u32,
+    ) -> Result<(), Rejection> {
+        let (corner, area) = courtyard_area(castle_lv);
+        if pos.x < corner.x
+            || pos.y < corner.y
+            || pos.x + size.x > corner.x + area.x
+            || pos.y + size.y > corner.y + area.y
+        {
+            return Err(Rejection::OutOfBounds);
         }
 
         for x in pos.x..pos.x + size.x {
             for y in pos.y..pos.y + size.y {
                 if self.occupied[y as usize][x as usize].is_some() {
-                    return false;
+                    return Err(Rejection::OccupiedPos);
                 }
             }
         }
-        true
+        Ok(())
     }
 
     fn mark_occupied(&mut self, id: u8, pos: GameCoord, size: GameCoord) {
@@ -117,7 +380,109 @@ impl Courtyard {
         }
     }
 
+    fn clear_occupied(&mut self, pos: GameCoord, size: GameCoord) {
+        for x in pos.x..pos.x + size.x {
+            for y in pos.y..pos.y + size.y {
+                self.occupied[y][x] = None;
+            }
+        }
+    }
+
     pub fn export(&self) -> HashMap<u8, Facility> {
         self.facilities.clone()
     }
+
+    pub fn export_build_queue(&self) -> Vec<BuildJob> {
+        self.build_queue.iter().cloned().collect()
+    }
+
+    pub fn export_training_queue(&self) -> Vec<TrainingJob> {
+        self.training_queue.clone()
+    }
+
+    // Idle peasants, free to be assigned or trained
+    pub fn get_peasants(&self) -> u32 {
+        self.peasants
+    }
+
+    // Idle peasants and workers
+    pub fn get_population(&self) -> u32 {
+        self.facilities
+            .values()
+            .fold(self.peasants, |acc, facility| {
+                acc.saturating_add(facility.workers)
+            })
+    }
+
+    // Sum of the levels of the built warehouses
+    pub fn get_warehouse_lv(&self) -> u32 {
+        self.facilities
+            .values()
+            .filter(|facility| facility.r#type == FacilityType::Warehouse)
+            .map(|facility| facility.lv)
+            .sum()
+    }
+
+    // Idle peasants die first, then workers. Returns the peasants lost.
+    pub fn starve_peasants(&mut self, percent: u32) -> u32 {
+        let population = self.get_population();
+        let lost = ((population as u64 * percent as u64 / 100) as u32)
+            .max(1)
+            .min(population);
+
+        let mut to_remove = lost;
+        let idle_lost = to_remove.min(self.peasants);
+        self.peasants -= idle_lost;
+        to_remove -= idle_lost;
+
+        let mut ids: Vec<u8> = self.facilities.keys().copied().collect();
+        ids.sort();
+        for id in ids {
+            if to_remove == 0 {
+                break;
+            }
+            if let Some(facility) = self.facilities.get_mut(&id) {
+                let workers_lost = to_remove.min(facility.workers);
+                facility.workers -= workers_lost;
+                to_remove -= workers_lost;
+            }
+        }
+        lost
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn demolishing_the_barracks_cancels_its_training() {
+        let mut courtyard = Courtyard::new();
+        let (corner, _) = courtyard_area(1);
+        courtyard
+            .new_facility(FacilityType::Barracks, corner, 1)
+            .unwrap();
+        while !courtyard.get_facility(1).unwrap().is_built() {
+            courtyard.update(true);
+        }
+        let peasants = courtyard.peasants;
+        courtyard.train_units(UnitType::Knight, 3).unwrap();
+        assert_eq!(courtyard.peasants, peasants - 3);
+
+        // Still a barracks, the job stays queued
+        assert!(
+            courtyard
+                .cancel_stalled_training(FacilityType::Barracks)
+                .is_empty()
+        );
+        assert_eq!(courtyard.training_queue.len(), 1);
+
+        courtyard.demolish_facility(1).unwrap();
+        assert_eq!(
+            courtyard.cancel_stalled_training(FacilityType::Barracks),
+            vec![(UnitType::Knight, 3)]
+        );
+        assert!(courtyard.training_queue.is_empty());
+        assert_eq!(courtyard.peasants, peasants);
+    }
 }
@@ -1,31 +1,50 @@
 use std::{
-    collections::{HashMap, VecDeque},
-    sync::mpsc::Receiver,
+    collections::{HashMap, HashSet},
+    sync::mpsc::{Receiver, TryRecvError},
 };
 
 use crate::{
+    r#const::{
+        ATTACK_WARNING_DISTANCE, PATHFINDING_TIMEOUT, PLUNDER_PERCENT, STARVATION_PERCENT,
+        UPKEEP_DESERTION_PERCENT,
+    },
     game::{
-        castle::Castle,
+        battle::{self, BattleResult},
+        castle::{self, Castle},
         game_obj::GameObj,
+        kit::StartingKit,
         map::Map,
         pathfinding,
-        units::{DeployedUnits, DeployedUnitsEvent},
+        units::{DeployedUnits, DeployedUnitsEvent, Order, Route},
     },
     thread_pool::ThreadPool,
 };
 use common::{
-    GameCoord, GameId, Time, r#const::CASTLE_SIZE, courtyard::FacilityType, game_objs::GameObjE,
-    packets::MapPayload, units::UnitGroup,
+    GameCoord, GameId, Time,
+    r#const::{CASTLE_SIZE, MAX_WAYPOINTS},
+    courtyard::FacilityType,
+    game_objs::{GameObjE, OwnedCastleE},
+    packets::{BattleOutcome, EventE, MapPayload, Rejection, RouteErr},
+    research::{self, TechType},
+    resources::Resources,
+    units::{UnitGroup, UnitType},
 };
 
 struct PathTask {
     pub units_id: GameId,
-    pub rx: Receiver<Option<VecDeque<GameCoord>>>,
+    pub order: Order,
+    pub rx: Receiver<Option<Route>>,
+    pub ticks_left: u32,
 }
 
 impl PathTask {
-    fn new(rx: Receiver<Option<VecDeque<GameCoord>>>, units_id: GameId) -> Self {
-        Self { rx, units_id }
+    fn new(rx: Receiver<Option<Route>>, units_id: GameId, order: Order) -> Self {
+        Self {
+            rx,
+            units_id,
+            order,
+            ticks_left: PATHFINDING_TIMEOUT,
+        }
     }
 }
 
@@ -35,6 +54,8 @@ pub struct Game {
     pathfinding_tasks: Vec<PathTask>,
     id_cnt: GameId,
     time: Time,
+    // Events for the owners of the castles, sent by the lobby
+    events: Vec<(GameId, EventE)>,
 }
 
 impl Game {
@@ -50,15 +71,23 @@ impl Game {
             pathfinding_tasks,
             id_cnt,
             time: Time::new(),
+            events: Vec::new(),
         }
     }
 
     // Returns a collection of dead castles ids
-    pub fn step(&mut self) -> Vec<GameId> {
+    pub fn step(&mut self, pool: &ThreadPool) -> Vec<GameId> {
         // Management of finished path tasks
-        self.pathfinding_tasks.retain(|task| {
-            let Ok(path) = task.rx.try_recv() else {
-                return true;
+        let mut units_to_home = Vec::new();
+        self.pathfinding_tasks.retain_mut(|task| {
+            let result = match task.rx.try_recv() {
+                Ok(Some(route)) => Ok(route),
+                Ok(None) | Err(TryRecvError::Disconnected) => Err(RouteErr::Unreachable),
+                Err(TryRecvError::Empty) if task.ticks_left == 0 => Err(RouteErr::Timeout),
+                Err(TryRecvError::Empty) => {
+                    task.ticks_left -= 1;
+                    return true;
+                }
             };
 
             let Some(GameObj::DeployedUnits(depl_units)) = self.game_objs.get_mut(&task.units_id)
@@ -66,18 +95,20 @@ impl Game {
                 return false;
             };
 
-            match path {
+            match result {
                 // The path was found
-                Some(path) => {
-                    depl_units.set_path(path);
+                Ok(route) => {
+                    depl_units.set_route(task.order, route);
                 }
-                // No path found, give back units to owner
-                None => {
-                    let owner_id = depl_units.get_owner_id();
-                    let units = depl_units.get_unit_group().clone();
-                    if let Some(GameObj::Castle(owner)) = self.game_objs.get_mut(&owner_id) {
-                        owner.add_units(&units);
+                // No path found. Units that never left go back to the owner,
+                // the others stay where they are.
+                Err(err) => {
+                    depl_units.stop_planning();
+                    if !depl_units.has_route() {
+                        units_to_home.push(task.units_id);
                     }
+                    self.events
+                        .push((depl_units.get_owner_id(), EventE::RouteErr(err)));
                 }
             }
 
@@ -86,122 +117,778 @@ impl Game {
 
         // Update game objects
         let mut dead_castles = Vec::new();
-        let mut units_to_home = Vec::new();
         let mut units_to_dest = Vec::new();
+        let units_away = self.get_units_away();
+        let no_units = UnitGroup::new();
+        let move_cost_percents: HashMap<GameId, u32> = self
+            .game_objs
+            .iter()
+            .filter_map(|(id, obj)| match obj {
+                GameObj::Castle(castle) => {
+                    Some((*id, research::move_cost_percent(castle.get_researched())))
+                }
+                _ => None,
+            })
+            .collect();
 
         for (id, obj) in self.game_objs.iter_mut() {
             match obj {
-                GameObj::DeployedUnits(deployed_units) => match deployed_units.step() {
-                    Some(DeployedUnitsEvent::AtDest) => {
-                        println!("SOME UNITS ARRIVED AT DEST, id:{}", id);
-                        units_to_dest.push((*id, deployed_units.clone()));
+                GameObj::DeployedUnits(deployed_units) => {
+                    let move_cost_percent = move_cost_percents
+                        .get(&deployed_units.get_owner_id())
+                        .copied()
+                        .unwrap_or(100);
+                    match deployed_units.step(&self.map, move_cost_percent) {
+                        Some(DeployedUnitsEvent::AtDest) => {
+                            println!("SOME UNITS ARRIVED AT DEST, id:{}", id);
+                            units_to_dest.push(*id);
+                        }
+                        Some(DeployedUnitsEvent::AtHome) => {
+                            println!("SOME UNITS ARRIVED AT HOME, id:{}", id);
+                            units_to_home.push(*id);
+                        }
+                        _ => {}
                     }
-                    Some(DeployedUnitsEvent::AtHome) => {
-                        println!("SOME UNITS ARRIVED AT HOME, id:{}", id);
-                        units_to_home.push((*id, deployed_units.clone()));
+                }
+                GameObj::Castle(castle) => {
+                    let away = units_away.get(id).unwrap_or(&no_units);
+                    for event in castle.update(away) {
+                        self.events.push((*id, event));
                     }
-                    _ => {}
-                },
-                GameObj::Castle(castle) => castle.update(),
+                }
                 _ => {}
             }
         }
 
-        for (id, deployed_units) in units_to_home.iter() {
+        let deserting: Vec<(GameId, bool, bool)> = self
+            .game_objs
+            .iter()
+            .filter_map(|(id, obj)| match obj {
+                GameObj::Castle(castle) if castle.is_upkeep_unpaid() || castle.is_starving() => {
+                    Some((*id, castle.is_upkeep_unpaid(), castle.is_starving()))
+                }
+                _ => None,
+            })
+            .collect();
+        for (owner_id, unpaid, starving) in deserting {
+            if unpaid {
+                self.desert_armies(owner_id, false);
+            }
+            if starving {
+                self.desert_armies(owner_id, true);
+            }
+        }
+
+        for id in units_to_home.iter() {
+            let Some(GameObj::DeployedUnits(deployed_units)) = self.game_objs.remove(id) else {
+                continue;
+            };
             let owner_id = deployed_units.get_owner_id();
             let Some(owner_castle) = self.get_castle_mut(owner_id) else {
                 continue;
             };
             owner_castle.add_units(deployed_units.get_unit_group());
-            self.game_objs.remove_entry(id);
+
+            let loot = deployed_units.get_loot();
+            if !loot.is_empty() {
+                owner_castle.add_resources(loot);
+                self.events
+                    .push((owner_id, EventE::LootDelivered { loot: loot.clone() }));
+            }
         }
 
-        for (_, deployed_units) in units_to_dest.iter() {
-            let Some(target_id) = deployed_units.get_target() else {
+        for id in units_to_dest.iter() {
+            let Some(deployed_units) = self.get_deployed_units(*id) else {
                 continue;
             };
-            let Some(target) = self.get_castle_mut(target_id) else {
+            let owner_id = deployed_units.get_owner_id();
+
+            match deployed_units.get_order() {
+                Order::Attack(target_id) => {
+                    if self.castle_battle(*id, target_id) {
+                        dead_castles.push(target_id);
+                    }
+                    // Defeated attackers don't come back
+                    if self.get_deployed_units(*id).is_none() {
+                        continue;
+                    }
+                }
+                // Units that can't join the garrison go back home
+                Order::Reinforce(target_id) => {
+                    if self.join_garrison(*id, target_id) {
+                        continue;
+                    }
+                }
+                // Units without a target hold their position
+                Order::Move | Order::Return => continue,
+            }
+
+            let Some(home_pos) = self.get_castle(owner_id).map(|castle| castle.get_pos()) else {
                 continue;
             };
-            let target_str = target.get_strength();
-            let attack_str = deployed_units.get_strength();
-            if target_str < attack_str {
-                target.kill();
-                dead_castles.push(target_id);
-                println!("Someone died :(");
-                println!("Attack str: {} | Def str: {}", attack_str, target_str);
-            }
+            let _ = self.route_units(*id, home_pos, Order::Return, pool);
         }
 
+        self.warn_approaching_attacks();
+        self.resolve_field_battles(pool);
+
         self.time.tick();
         dead_castles
     }
 
+    // Returns true if the castle fell
+    fn castle_battle(&mut self, units_id: GameId, castle_id: GameId) -> bool {
+        let Some(owner_id) = self
+            .get_deployed_units(units_id)
+            .map(|units| units.get_owner_id())
+        else {
+            return false;
+        };
+        let bonus = [
+            self.get_strength_bonus(owner_id),
+            self.get_strength_bonus(castle_id),
+        ];
+        let [
+            Some(GameObj::DeployedUnits(units)),
+            Some(GameObj::Castle(castle)),
+        ] = self.game_objs.get_disjoint_mut([&units_id, &castle_id])
+        else {
+            return false;
+        };
+        if !castle.is_alive() {
+            return false;
+        }
+
+        let mut defenders = castle.get_defenders();
+        let Some(BattleResult {
+            mut outcomes,
+            losses,
+        }) = battle::fight(units.get_unit_group_mut(), &mut defenders, bonus)
+        else {
+            return false;
+        };
+        castle.take_defence_losses(&losses[1]);
+        let units_left = !units.get_unit_group().is_empty();
+        let defender = castle.get_name().to_string();
+
+        let castle_fell = outcomes[0] == BattleOutcome::Victory;
+        if castle_fell {
+            // A castle can't retreat
+            outcomes[1] = BattleOutcome::Defeat;
+            castle.kill();
+            println!("Someone died :(");
+        }
+        // Surviving attackers carry off part of the resources, less if they retreated
+        let loot = match outcomes[0] {
+            _ if !units_left => None,
+            BattleOutcome::Victory => Some(PLUNDER_PERCENT),
+            BattleOutcome::Retreat => Some(PLUNDER_PERCENT / 2),
+            BattleOutcome::Defeat => None,
+        }
+        .map(|percent| {
+            let loot = castle.plunder(percent, units.get_free_capacity());
+            units.add_loot(&loot);
+            loot
+        })
+        .filter(|loot| !loot.is_empty());
+
+        if outcomes[0] == BattleOutcome::Defeat || !units_left {
+            self.game_objs.remove(&units_id);
+        }
+
+        let attacker = self
+            .get_castle(owner_id)
+            .map_or_else(String::new, |castle| castle.get_name().to_string());
+        for (side, receiver_id) in [owner_id, castle_id].into_iter().enumerate() {
+            self.events.push((
+                receiver_id,
+                EventE::CastleBattle {
+                    attacker: attacker.clone(),
+                    defender: defender.clone(),
+                    outcome: outcomes[side],
+                    losses: losses[side].clone(),
+                    enemy_losses: losses[1 - side].clone(),
+                },
+            ));
+        }
+        if let Some(loot) = loot {
+            self.events.push((
+                owner_id,
+                EventE::Plundered {
+                    target: defender,
+                    loot: loot.clone(),
+                },
+            ));
+            self.events
+                .push((castle_id, EventE::Pillaged { attacker, loot }));
+        }
+        castle_fell
+    }
+
+    fn warn_attack_dispatched(&mut self, owner_id: GameId, castle_id: GameId) {
+        let Some(attacker) = self
+            .get_castle(owner_id)
+            .map(|castle| castle.get_name().to_string())
+        else {
+            return;
+        };
+        self.events
+            .push((castle_id, EventE::AttackDispatched { attacker }));
+    }
+
+    fn warn_approaching_attacks(&mut self) {
+        let mut warnings = Vec::new();
+        for (id, obj) in self.game_objs.iter() {
+            let GameObj::DeployedUnits(units) = obj else {
+                continue;
+            };
+            let Order::Attack(target_id) = units.get_order() else {
+                continue;
+            };
+            if units.is_warned(target_id) {
+                continue;
+            }
+            let Some(target_pos) = self.get_castle(target_id).map(|castle| castle.get_pos()) else {
+                continue;
+            };
+            let pos = units.get_pos();
+            let distance = pos
+                .x
+                .abs_diff(target_pos.x)
+                .max(pos.y.abs_diff(target_pos.y));
+            if distance <= ATTACK_WARNING_DISTANCE {
+                warnings.push((*id, units.get_owner_id(), target_id, distance));
+            }
+        }
+
+        for (units_id, owner_id, target_id, distance) in warnings {
+            if let Some(GameObj::DeployedUnits(units)) = self.game_objs.get_mut(&units_id) {
+                units.set_warned(target_id);
+            }
+            let Some(attacker) = self
+                .get_castle(owner_id)
+                .map(|castle| castle.get_name().to_string())
+            else {
+                continue;
+            };
+            self.events
+                .push((target_id, EventE::AttackApproaching { attacker, distance }));
+        }
+    }
+
+    fn join_garrison(&mut self, units_id: GameId, castle_id: GameId) -> bool {
+        let Some(owner_id) = self
+            .get_deployed_units(units_id)
+            .map(|units| units.get_owner_id())
+        else {
+            return false;
+        };
+        let Some(owner_name) = self
+            .get_castle(owner_id)
+            .map(|castle| castle.get_name().to_string())
+        else {
+            return false;
+        };
+        if !self
+            .get_castle(castle_id)
+            .is_some_and(|castle| castle.is_alive())
+        {
+            return false;
+        }
+
+        let Some(GameObj::DeployedUnits(units)) = self.game_objs.remove(&units_id) else {
+            return false;
+        };
+        let Some(castle) = self.get_castle_mut(castle_id) else {
+            return false;
+        };
+        castle.add_reinforcements(owner_id, units.get_unit_group());
+        self.events.push((
+            castle_id,
+            EventE::ReinforcementsArrived {
+                from: owner_name,
+                units: units.get_unit_group().clone(),
+            },
+        ));
+        true
+    }
+
+    // Hostile armies on the same or on adjacent tiles fight.
+    // Each army fights at most one battle per tick.
+    fn resolve_field_battles(&mut self, pool: &ThreadPool) {
+        let mut armies: Vec<(GameId, GameId, GameCoord)> = self
+            .game_objs
+            .iter()
+            .filter_map(|(id, obj)| match obj {
+                GameObj::DeployedUnits(units) if units.has_route() && !units.is_disengaged() => {
+                    Some((*id, units.get_owner_id(), units.get_pos()))
+                }
+                _ => None,
+            })
+            .collect();
+        armies.sort_by_key(|army| army.0);
+
+        let mut fought = HashSet::new();
+        for (i, (id, owner_id, pos)) in armies.iter().enumerate() {
+            if fought.contains(id) {
+                continue;
+            }
+            let Some((enemy_id, _, _)) =
+                armies[i + 1..]
+                    .iter()
+                    .find(|(other_id, other_owner, other_pos)| {
+                        other_owner != owner_id
+                            && !self.are_allied(*owner_id, *other_owner)
+                            && !fought.contains(other_id)
+                            && pos.x.abs_diff(other_pos.x) <= 1
+                            && pos.y.abs_diff(other_pos.y) <= 1
+                    })
+            else {
+                continue;
+            };
+            fought.insert(*id);
+            fought.insert(*enemy_id);
+            self.field_battle([*id, *enemy_id], pool);
+        }
+    }
+
+    // Players lending units to each other don't fight in the field
+    fn are_allied(&self, a: GameId, b: GameId) -> bool {
+        let lends_to = |lender, host| {
+            self.get_castle(host)
+                .is_some_and(|castle| castle.get_reinforcements(lender).is_some())
+        };
+        lends_to(a, b) || lends_to(b, a)
+    }
+
+    fn field_battle(&mut self, ids: [GameId; 2], pool: &ThreadPool) {
+        let bonus = ids.map(|id| {
+            self.get_deployed_units(id)
+                .map_or([0; UnitType::COUNT], |units| {
+                    self.get_strength_bonus(units.get_owner_id())
+                })
+        });
+        let [
+            Some(GameObj::DeployedUnits(army_a)),
+            Some(GameObj::DeployedUnits(army_b)),
+        ] = self.game_objs.get_disjoint_mut([&ids[0], &ids[1]])
+        else {
+            return;
+        };
+
+        let owners = [army_a.get_owner_id(), army_b.get_owner_id()];
+        let pos = army_a.get_pos();
+        let Some(BattleResult { outcomes, losses }) = battle::fight(
+            army_a.get_unit_group_mut(),
+            army_b.get_unit_group_mut(),
+            bonus,
+        ) else {
+            return;
+        };
+        println!("Field battle at {}: {:?}", pos, outcomes);
+
+        for (side, id) in ids.iter().enumerate() {
+            let is_empty = self
+                .get_deployed_units(*id)
+                .is_none_or(|units| units.get_unit_group().is_empty());
+
+            match outcomes[side] {
+                _ if is_empty => {
+                    self.game_objs.remove(id);
+                }
+                BattleOutcome::Defeat => {
+                    self.game_objs.remove(id);
+                }
+                BattleOutcome::Retreat => {
+                    if let Some(GameObj::DeployedUnits(units)) = self.game_objs.get_mut(id) {
+                        units.disengage();
+                    }
+                    if let Some(home_pos) =
+                        self.get_castle(owners[side]).map(|castle| castle.get_pos())
+                    {
+                        let _ = self.route_units(*id, home_pos, Order::Return, pool);
+                    }
+                }
+                BattleOutcome::Victory => {}
+            }
+
+            self.events.push((
+                owners[side],
+                EventE::FieldBattle {
+                    pos,
+                    outcome: outcomes[side],
+                    losses: losses[side].clone(),
+                    enemy_losses: losses[1 - side].clone(),
+                },
+            ));
+        }
+    }
+
     pub fn attack_castle(
         &mut self,
         attacker_id: GameId,
         target_id: GameId,
         unit_group_e: UnitGroup,
         pool: &ThreadPool,
-    ) -> bool {
-        let Some(target_pos) = self.get_castle(target_id).map(|castle| castle.get_pos()) else {
-            return false;
-        };
-        self.request_send_units(attacker_id, target_pos, unit_group_e, Some(target_id), pool)
+    ) -> Result<(), Rejection> {
+        let target_pos = self.get_target_castle_pos(attacker_id, target_id)?;
+        self.request_send_units(
+            attacker_id,
+            vec![target_pos],
+            false,
+            unit_group_e,
+            Some(target_id),
+            pool,
+        )
     }
 
     pub fn request_send_units(
         &mut self,
         attacker_id: GameId,
-        target_pos: GameCoord,
+        waypoints: Vec<GameCoord>,
+        patrol: bool,
         unit_group: UnitGroup,
         target_id: Option<GameId>,
         pool: &ThreadPool,
-    ) -> bool {
-        if unit_group.is_empty() {
-            return false;
+    ) -> Result<(), Rejection> {
+        let order = match target_id {
+            Some(target_id) => Order::Attack(target_id),
+            None => Order::Move,
+        };
+        self.deploy_units(attacker_id, waypoints, patrol, unit_group, order, pool)?;
+        if let Some(target_id) = target_id {
+            self.warn_attack_dispatched(attacker_id, target_id);
         }
+        Ok(())
+    }
 
-        if self.map.is_obstacle(target_pos) {
-            return false;
+    pub fn reinforce_castle(
+        &mut self,
+        owner_id: GameId,
+        castle_id: GameId,
+        unit_group: UnitGroup,
+        pool: &ThreadPool,
+    ) -> Result<(), Rejection> {
+        let castle_pos = self.get_target_castle_pos(owner_id, castle_id)?;
+        self.deploy_units(
+            owner_id,
+            vec![castle_pos],
+            false,
+            unit_group,
+            Order::Reinforce(castle_id),
+            pool,
+        )
+    }
+
+    // The reinforcements leave the castle and go back home
+    pub fn recall_reinforcements(
+        &mut self,
+        owner_id: GameId,
+        castle_id: GameId,
+        pool: &ThreadPool,
+    ) -> Result<(), Rejection> {
+        let home_pos = self
+            .get_castle(owner_id)
+            .map(|castle| castle.get_pos())
+            .ok_or(Rejection::NoCastle)?;
+        let castle = self
+            .get_castle_mut(castle_id)
+            .ok_or(Rejection::InvalidTarget)?;
+        let unit_group = castle
+            .take_reinforcements(owner_id)
+            .ok_or(Rejection::NothingToRecall)?;
+
+        let castle_pos = castle.get_pos();
+        let id = Self::new_id(&mut self.id_cnt);
+        let deployed_units = DeployedUnits::new(owner_id, Order::Return, castle_pos, unit_group);
+        self.game_objs
+            .insert(id, GameObj::DeployedUnits(deployed_units));
+
+        self.route_units(id, home_pos, Order::Return, pool)
+    }
+
+    pub fn gift_resources(
+        &mut self,
+        owner_id: GameId,
+        castle_id: GameId,
+        resources: Resources,
+    ) -> Result<(), Rejection> {
+        self.get_target_castle_pos(owner_id, castle_id)?;
+        let owner_castle = self.get_castle_mut(owner_id).ok_or(Rejection::NoCastle)?;
+        if !owner_castle.subtract_resources_if_enough(&resources) {
+            return Err(Rejection::NotEnoughResources);
         }
+        let owner_name = owner_castle.get_name().to_string();
 
-        let Some(attacker_castle) = self.get_castle_mut(attacker_id) else {
-            return false;
-        };
+        let castle = self
+            .get_castle_mut(castle_id)
+            .ok_or(Rejection::InvalidTarget)?;
+        castle.add_resources(&resources);
+        self.events.push((
+            castle_id,
+            EventE::GiftReceived {
+                from: owner_name,
+                resources,
+            },
+        ));
+        Ok(())
+    }
 
-        if !attacker_castle.subtract_units_if_enough(&unit_group) {
-            return false;
+    // Position of a living castle that isn't the player one
+    fn get_target_castle_pos(
+        &self,
+        owner_id: GameId,
+        castle_id: GameId,
+    ) -> Result<GameCoord, Rejection> {
+        if owner_id == castle_id {
+            return Err(Rejection::InvalidTarget);
+        }
+        self.get_castle(castle_id)
+            .filter(|castle| castle.is_alive())
+            .map(|castle| castle.get_pos())
+            .ok_or(Rejection::InvalidTarget)
+    }
+
+    fn deploy_units(
+        &mut self,
+        owner_id: GameId,
+        waypoints: Vec<GameCoord>,
+        patrol: bool,
+        unit_group: UnitGroup,
+        order: Order,
+        pool: &ThreadPool,
+    ) -> Result<(), Rejection> {
+        if unit_group.is_empty() {
+            return Err(Rejection::NotEnoughUnits);
         }
+        self.check_waypoints(&waypoints)?;
 
-        let deployed_units = DeployedUnits::new(attacker_id, target_id, None, unit_group);
-        let attacker_pos = attacker_castle.get_pos();
-        let map_obstacles = self.map.get_obstacles().clone();
+        let owner_castle = self.get_castle_mut(owner_id).ok_or(Rejection::NoCastle)?;
+
+        if !owner_castle.subtract_units_if_enough(&unit_group) {
+            return Err(Rejection::NotEnoughUnits);
+        }
+
+        let owner_pos = owner_castle.get_pos();
         let id = Self::new_id(&mut self.id_cnt);
+        let deployed_units = DeployedUnits::new(owner_id, order, owner_pos, unit_group);
 
         self.game_objs
             .insert(id, GameObj::DeployedUnits(deployed_units));
 
+        self.route_units_through(id, waypoints, patrol, order, pool)
+    }
+
+    pub fn recall_units(
+        &mut self,
+        owner_id: GameId,
+        units_id: GameId,
+        pool: &ThreadPool,
+    ) -> Result<(), Rejection> {
+        self.check_owner_of_units(owner_id, units_id)?;
+        let home_pos = self
+            .get_castle(owner_id)
+            .map(|castle| castle.get_pos())
+            .ok_or(Rejection::NoCastle)?;
+        self.route_units(units_id, home_pos, Order::Return, pool)
+    }
+
+    pub fn redirect_attack(
+        &mut self,
+        owner_id: GameId,
+        units_id: GameId,
+        target_id: GameId,
+        pool: &ThreadPool,
+    ) -> Result<(), Rejection> {
+        let target_pos = self.get_target_castle_pos(owner_id, target_id)?;
+        self.redirect_units(owner_id, units_id, target_pos, Some(target_id), pool)
+    }
+
+    pub fn redirect_units(
+        &mut self,
+        owner_id: GameId,
+        units_id: GameId,
+        target_pos: GameCoord,
+        target_id: Option<GameId>,
+        pool: &ThreadPool,
+    ) -> Result<(), Rejection> {
+        self.check_owner_of_units(owner_id, units_id)?;
+        let order = match target_id {
+            Some(target_id) => Order::Attack(target_id),
+            None => Order::Move,
+        };
+        self.route_units(units_id, target_pos, order, pool)?;
+        if let Some(target_id) = target_id {
+            self.warn_attack_dispatched(owner_id, target_id);
+        }
+        Ok(())
+    }
+
+    fn route_units(
+        &mut self,
+        units_id: GameId,
+        dest: GameCoord,
+        order: Order,
+        pool: &ThreadPool,
+    ) -> Result<(), Rejection> {
+        self.route_units_through(units_id, vec![dest], false, order, pool)
+    }
+
+    // The first units join the second ones, on the same or on an adjacent tile
+    pub fn merge_units(
+        &mut self,
+        owner_id: GameId,
+        units_id: GameId,
+        into_id: GameId,
+    ) -> Result<(), Rejection> {
+        if units_id == into_id {
+            return Err(Rejection::InvalidTarget);
+        }
+        self.check_owner_of_units(owner_id, units_id)?;
+        self.check_owner_of_units(owner_id, into_id)?;
+        let [
+            Some(GameObj::DeployedUnits(units)),
+            Some(GameObj::DeployedUnits(into)),
+        ] = self.game_objs.get_disjoint_mut([&units_id, &into_id])
+        else {
+            return Err(Rejection::NotYourUnits);
+        };
+        // Units still waiting for their first route are not on the map yet
+        if !units.has_route() || !into.has_route() {
+            return Err(Rejection::UnitsNotDeployed);
+        }
+        let (pos, into_pos) = (units.get_pos(), into.get_pos());
+        if pos.x.abs_diff(into_pos.x) > 1 || pos.y.abs_diff(into_pos.y) > 1 {
+            return Err(Rejection::TooFar);
+        }
+        if !into.merge(units.clone()) {
+            return Err(Rejection::CannotSail);
+        }
+
+        self.game_objs.remove(&units_id);
+        self.pathfinding_tasks
+            .retain(|task| task.units_id != units_id);
+        Ok(())
+    }
+
+    // Returns the id of the new units
+    pub fn split_units(
+        &mut self,
+        owner_id: GameId,
+        units_id: GameId,
+        unit_group: UnitGroup,
+    ) -> Result<GameId, Rejection> {
+        self.check_owner_of_units(owner_id, units_id)?;
+        let Some(GameObj::DeployedUnits(units)) = self.game_objs.get_mut(&units_id) else {
+            return Err(Rejection::NotYourUnits);
+        };
+        if !units.has_route() {
+            return Err(Rejection::UnitsNotDeployed);
+        }
+        let new_units = units.split(&unit_group).ok_or(Rejection::NotEnoughUnits)?;
+
+        let id = Self::new_id(&mut self.id_cnt);
+        self.game_objs.insert(id, GameObj::DeployedUnits(new_units));
+        Ok(id)
+    }
+
+    // Stops the units and computes a new route from their current position.
+    // The order is applied once the route is found.
+    fn route_units_through(
+        &mut self,
+        units_id: GameId,
+        waypoints: Vec<GameCoord>,
+        patrol: bool,
+        order: Order,
+        pool: &ThreadPool,
+    ) -> Result<(), Rejection> {
+        self.check_waypoints(&waypoints)?;
+        let Some(GameObj::DeployedUnits(deployed_units)) = self.game_objs.get_mut(&units_id) else {
+            return Err(Rejection::NotYourUnits);
+        };
+
+        deployed_units.halt();
+        let start = deployed_units.get_pos();
+        let naval = deployed_units.get_unit_group().can_sail();
+        let map_tiles = self.map.get_tiles().clone();
+
+        // A new order replaces any route still being computed
+        self.pathfinding_tasks
+            .retain(|task| task.units_id != units_id);
+
         let task = PathTask::new(
             pool.execute_with_result(move || {
-                pathfinding::a_star(attacker_pos, target_pos, &map_obstacles)
+                let path = pathfinding::a_star_waypoints(start, &waypoints, &map_tiles, naval)?;
+                let patrol = match waypoints.last() {
+                    Some(&last) if patrol => Some(pathfinding::a_star_waypoints(
+                        last, &waypoints, &map_tiles, naval,
+                    )?),
+                    _ => None,
+                };
+                Some(Route {
+                    path,
+                    waypoints: waypoints.into(),
+                    patrol,
+                })
             }),
-            id,
+            units_id,
+            order,
         );
         self.pathfinding_tasks.push(task);
 
-        true
+        Ok(())
+    }
+
+    fn check_waypoints(&self, waypoints: &[GameCoord]) -> Result<(), Rejection> {
+        if waypoints.is_empty() {
+            return Err(Rejection::InvalidTarget);
+        }
+        if waypoints.len() > MAX_WAYPOINTS {
+            return Err(Rejection::TooManyWaypoints);
+        }
+        if waypoints
+            .iter()
+            .any(|pos| self.map.move_cost(*pos).is_none())
+        {
+            return Err(Rejection::Route(RouteErr::TargetObstacle));
+        }
+        Ok(())
     }
 
-    pub fn add_player_castle(&mut self, name: String, pos: GameCoord) -> Option<GameId> {
-        if !pos.is_even() || !self.map.can_build(pos, CASTLE_SIZE) {
-            return None;
+    fn check_owner_of_units(&self, owner_id: GameId, units_id: GameId) -> Result<(), Rejection> {
+        match self.get_deployed_units(units_id) {
+            Some(units) if units.get_owner_id() == owner_id => Ok(()),
+            _ => Err(Rejection::NotYourUnits),
+        }
+    }
+
+    pub fn add_player_castle(
+        &mut self,
+        name: String,
+        pos: GameCoord,
+        kit: &StartingKit,
+    ) -> Result<GameId, Rejection> {
+        if !pos.is_even() {
+            return Err(Rejection::NotEvenCoord);
+        }
+        if !self.map.can_build(pos, CASTLE_SIZE) {
+            return Err(Rejection::OccupiedPos);
         }
         self.map.set_occupied(pos, CASTLE_SIZE);
         let id = Self::new_id(&mut self.id_cnt);
-        let castle = Castle::new(name, pos);
+        let castle = Castle::new(name, pos, kit);
         self.game_objs.insert(id, GameObj::Castle(castle));
-        Some(id)
+        Ok(id)
+    }
+
+    pub fn grant_kit(&mut self, castle_id: GameId, kit_name: &str) -> Result<(), Rejection> {
+        let kit = StartingKit::find(kit_name).ok_or(Rejection::UnknownKit)?;
+        let castle = self
+            .get_castle_mut(castle_id)
+            .ok_or(Rejection::InvalidTarget)?;
+
+        castle.add_kit(kit);
+        Ok(())
     }
 
     pub fn add_facility(
@@ -209,14 +896,83 @@ impl Game {
         castle_id: GameId,
         facility_type: FacilityType,
         pos: GameCoord,
-    ) -> bool {
-        let Some(castle) = self.get_castle_mut(castle_id) else {
-            return false;
-        };
+    ) -> Result<(), Rejection> {
+        let castle = self.get_castle_mut(castle_id).ok_or(Rejection::NoCastle)?;
 
         castle.new_facility(facility_type, pos)
     }
 
+    pub fn upgrade_facility(
+        &mut self,
+        castle_id: GameId,
+        facility_id: u8,
+    ) -> Result<(), Rejection> {
+        let castle = self.get_castle_mut(castle_id).ok_or(Rejection::NoCastle)?;
+
+        castle.upgrade_facility(facility_id)
+    }
+
+    pub fn demolish_facility(
+        &mut self,
+        castle_id: GameId,
+        facility_id: u8,
+    ) -> Result<(), Rejection> {
+        let castle = self.get_castle_mut(castle_id).ok_or(Rejection::NoCastle)?;
+
+        castle.demolish_facility(facility_id)
+    }
+
+    pub fn relocate_facility(
+        &mut self,
+        castle_id: GameId,
+        facility_id: u8,
+        pos: GameCoord,
+    ) -> Result<(), Rejection> {
+        let castle = self.get_castle_mut(castle_id).ok_or(Rejection::NoCastle)?;
+
+        castle.relocate_facility(facility_id, pos)
+    }
+
+    pub fn research(&mut self, castle_id: GameId, tech: TechType) -> Result<(), Rejection> {
+        let castle = self.get_castle_mut(castle_id).ok_or(Rejection::NoCastle)?;
+
+        castle.research(tech)
+    }
+
+    pub fn upgrade_castle(&mut self, castle_id: GameId) -> Result<(), Rejection> {
+        let castle = self.get_castle_mut(castle_id).ok_or(Rejection::NoCastle)?;
+
+        castle.upgrade()
+    }
+
+    pub fn assign_workers(
+        &mut self,
+        castle_id: GameId,
+        facility_id: u8,
+        workers: u32,
+    ) -> Result<(), Rejection> {
+        let castle = self.get_castle_mut(castle_id).ok_or(Rejection::NoCastle)?;
+
+        castle.assign_workers(facility_id, workers)
+    }
+
+    pub fn train_units(
+        &mut self,
+        castle_id: GameId,
+        unit: UnitType,
+        count: u32,
+    ) -> Result<(), Rejection> {
+        let castle = self.get_castle_mut(castle_id).ok_or(Rejection::NoCastle)?;
+
+        castle.train_units(unit, count)
+    }
+
+    pub fn cancel_training(&mut self, castle_id: GameId, job_id: u32) -> Result<(), Rejection> {
+        let castle = self.get_castle_mut(castle_id).ok_or(Rejection::NoCastle)?;
+
+        castle.cancel_training(job_id)
+    }
+
     pub fn get_castle(&self, castle_id: GameId) -> Option<&Castle> {
         self.game_objs
             .iter()
@@ -243,6 +999,125 @@ impl Game {
             })
     }
 
+    // Deployed units and units in other garrisons, by owner castle
+    fn get_units_away(&self) -> HashMap<GameId, UnitGroup> {
+        let mut units_away: HashMap<GameId, UnitGroup> = HashMap::new();
+        for obj in self.game_objs.values() {
+            match obj {
+                GameObj::DeployedUnits(units) => units_away
+                    .entry(units.get_owner_id())
+                    .or_insert_with(UnitGroup::new)
+                    .saturating_add(units.get_unit_group()),
+                GameObj::Castle(castle) => {
+                    for (owner_id, units) in castle.iter_reinforcements() {
+                        units_away
+                            .entry(*owner_id)
+                            .or_insert_with(UnitGroup::new)
+                            .saturating_add(units);
+                    }
+                }
+                _ => {}
+            }
+        }
+        units_away
+    }
+
+    // Part of the deployed armies and of the lent units of a castle that can't
+    // pay their upkeep or feed them deserts.
+    // The deserters are added to the event the castle already raised.
+    fn desert_armies(&mut self, owner_id: GameId, starving: bool) {
+        let mut deserters = UnitGroup::new();
+        let mut disbanded = Vec::new();
+        for (id, obj) in self.game_objs.iter_mut() {
+            let units = match obj {
+                GameObj::DeployedUnits(units) => units,
+                GameObj::Castle(castle) => {
+                    deserters.saturating_add(&castle.desert_reinforcements(owner_id, starving));
+                    continue;
+                }
+                _ => continue,
+            };
+            if units.get_owner_id() != owner_id {
+                continue;
+            }
+            let mut army_deserters = if starving {
+                castle::starve(units.get_unit_group(), STARVATION_PERCENT)
+            } else {
+                castle::desert(units.get_unit_group(), UPKEEP_DESERTION_PERCENT)
+            };
+            // Ships at sea can't be abandoned
+            if units.is_embarked() {
+                let ships = army_deserters.quantities[UnitType::Ship.as_index()];
+                army_deserters.subtract_single_type(UnitType::Ship, ships);
+            }
+            units
+                .get_unit_group_mut()
+                .subtract_unchecked(&army_deserters);
+            deserters.saturating_add(&army_deserters);
+            if units.get_unit_group().is_empty() {
+                disbanded.push(*id);
+            }
+        }
+        for id in disbanded {
+            self.game_objs.remove(&id);
+        }
+
+        let total = self
+            .events
+            .iter_mut()
+            .rev()
+            .find_map(|(id, event)| match event {
+                EventE::UpkeepUnpaid { deserters } if *id == owner_id && !starving => {
+                    Some(deserters)
+                }
+                EventE::Starvation { deserters, .. } if *id == owner_id && starving => {
+                    Some(deserters)
+                }
+                _ => None,
+            });
+        if let Some(total) = total {
+            total.saturating_add(&deserters);
+        }
+    }
+
+    // Strength bonus percents of the castle units, indexed by unit type
+    fn get_strength_bonus(&self, castle_id: GameId) -> [u32; UnitType::COUNT] {
+        self.get_castle(castle_id)
+            .map_or([0; UnitType::COUNT], |castle| {
+                research::strength_bonus_percent(castle.get_researched())
+            })
+    }
+
+    // Units of the castle in the garrisons of other castles
+    pub fn get_lent_units(&self, owner_id: GameId) -> HashMap<GameId, UnitGroup> {
+        self.game_objs
+            .iter()
+            .filter_map(|(id, obj)| match obj {
+                GameObj::Castle(castle) => castle
+                    .get_reinforcements(owner_id)
+                    .map(|units| (*id, units.clone())),
+                _ => None,
+            })
+            .collect()
+    }
+
+    pub fn export_owned_castle(&self, castle_id: GameId) -> Option<OwnedCastleE> {
+        let mut castle_e = self.get_castle(castle_id)?.export_owned();
+        castle_e.lent_units = self.get_lent_units(castle_id);
+        Some(castle_e)
+    }
+
+    pub fn get_deployed_units(&self, units_id: GameId) -> Option<&DeployedUnits> {
+        match self.game_objs.get(&units_id) {
+            Some(GameObj::DeployedUnits(deployed_units)) => Some(deployed_units),
+            _ => None,
+        }
+    }
+
+    pub fn take_events(&mut self) -> Vec<(GameId, EventE)> {
+        std::mem::take(&mut self.events)
+    }
+
     pub fn get_time(&self) -> Time {
         self.time
     }
@@ -251,10 +1126,10 @@ impl Game {
         self.map.export()
     }
 
-    pub fn export_objs(&self) -> HashMap<GameId, GameObjE> {
+    pub fn export_objs(&self, viewer_id: Option<GameId>) -> HashMap<GameId, GameObjE> {
         self.game_objs
             .iter()
-            .filter_map(|(&id, game_obj)| game_obj.export().map(|obj_e| (id, obj_e)))
+            .filter_map(|(&id, game_obj)| game_obj.export(viewer_id).map(|obj_e| (id, obj_e)))
             .collect()
     }
 
@@ -1,4 +1,4 @@
-use common::game_objs::GameObjE;
+use common::{GameId, game_objs::GameObjE};
 
 use crate::game::{castle::Castle, structure::Structure, units::DeployedUnits};
 
@@ -9,17 +9,14 @@ pub enum GameObj {
 }
 
 impl GameObj {
-    pub fn export(&self) -> Option<GameObjE> {
+    // viewer_id is the castle of the player receiving the export
+    pub fn export(&self, viewer_id: Option<GameId>) -> Option<GameObjE> {
         match self {
             Self::Castle(castle) => Some(GameObjE::Castle(castle.export())),
             Self::Structure(structure) => Some(GameObjE::Structure(structure.export())),
-            Self::DeployedUnits(deployed_units) => {
-                if let Some(export) = deployed_units.export() {
-                    return Some(GameObjE::DeployedUnits(export));
-                } else {
-                    None
-                }
-            }
+            Self::DeployedUnits(deployed_units) => deployed_units
+                .export(viewer_id)
+                .map(GameObjE::DeployedUnits),
         }
     }
 }
@@ -0,0 +1,26 @@
+use common::{
+    resources::Resources,
+    units::{UnitGroup, UnitType},
+};
+
+use crate::r#const::STARTING_KITS;
+
+// Resources and units given to a castle, on creation or by an admin
+pub struct StartingKit {
+    pub name: &'static str,
+    pub resources: Resources,
+    // Indexed like UnitGroup
+    pub units: [u32; UnitType::COUNT],
+}
+
+impl StartingKit {
+    pub fn find(name: &str) -> Option<&'static StartingKit> {
+        STARTING_KITS.iter().find(|kit| kit.name == name)
+    }
+
+    pub fn get_units(&self) -> UnitGroup {
+        let mut units = UnitGroup::new();
+        units.quantities = self.units;
+        units
+    }
+}
@@ -18,7 +18,7 @@ impl Map {
         let tiles = map_gen::generate_tiles();
         let obstacles: Vec<Vec<bool>> = tiles
             .iter()
-            .map(|row| row.iter().map(|t| *t == Tile::Water).collect())
+            .map(|row| row.iter().map(|t| t.move_cost().is_none()).collect())
             .collect();
         let occupied = vec![vec![false; MAP_COLS]; MAP_ROWS];
 
@@ -30,6 +30,20 @@ impl Map {
         }
     }
 
+    #[cfg(test)]
+    pub fn from_tiles(tiles: Vec<Vec<Tile>>) -> Self {
+        let obstacles = tiles
+            .iter()
+            .map(|row| row.iter().map(|t| t.move_cost().is_none()).collect())
+            .collect();
+        let occupied = tiles.iter().map(|row| vec![false; row.len()]).collect();
+        Self {
+            tiles,
+            obstacles,
+            occupied,
+        }
+    }
+
     pub fn is_obstacle(&self, pos: GameCoord) -> bool {
         self.obstacles
             .get(pos.y)
@@ -82,8 +96,8 @@ impl Map {
         }
     }
 
-    pub fn get_obstacles(&self) -> &Vec<Vec<bool>> {
-        &self.obstacles
+    pub fn get_tiles(&self) -> &Vec<Vec<Tile>> {
+        &self.tiles
     }
 
     pub fn get_tile(&self, pos: GameCoord) -> Option<Tile> {
@@ -93,6 +107,10 @@ impl Map {
             .copied()
     }
 
+    pub fn move_cost(&self, pos: GameCoord) -> Option<u32> {
+        self.get_tile(pos).and_then(|tile| tile.move_cost())
+    }
+
     pub fn export(&self) -> MapPayload {
         let rows = self.tiles.len();
         let cols = self.tiles.first().map(|row| row.len()).unwrap_or(0);
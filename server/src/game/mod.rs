@@ -1,9 +1,12 @@
+mod battle;
 mod castle;
 mod courtyard;
 pub mod game;
 mod game_obj;
+pub mod kit;
 mod map;
 mod map_gen;
 mod pathfinding;
+mod research;
 mod structure;
 mod units;
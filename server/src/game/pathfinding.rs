@@ -3,14 +3,15 @@ use std::collections::BinaryHeap;
 use std::collections::{HashMap, VecDeque};
 
 use common::GameCoord;
-use common::r#const::{MAP_COLS, MAP_ROWS};
+use common::r#const::{EMBARK_COST, GRASS_MOVE_COST, MAP_COLS, MAP_ROWS, WATER_MOVE_COST};
+use common::map::Tile;
 
 #[derive(Clone)]
 struct Node {
     coord: GameCoord,
     prev: Option<GameCoord>,
-    g: u16,
-    f: u16,
+    g: u32,
+    f: u32,
 }
 
 impl PartialOrd for Node {
@@ -33,14 +34,46 @@ impl PartialEq for Node {
 
 impl Eq for Node {}
 
-#[allow(dead_code)]
+// Cost of moving between two adjacent tiles. Land and water are two movement
+// modes: switching between them means embarking or disembarking, which only
+// naval armies can do and only from a coast tile.
+pub fn step_cost(from: Tile, to: Tile, naval: bool) -> Option<u32> {
+    match (from, to) {
+        (Tile::Water, Tile::Water) if naval => Some(WATER_MOVE_COST),
+        (_, Tile::Water) if naval => Some(WATER_MOVE_COST + EMBARK_COST),
+        (Tile::Water, _) => to.move_cost().map(|cost| cost + EMBARK_COST),
+        _ => to.move_cost(),
+    }
+}
+
+// The cost of a step is given by step_cost, mostly the tile being entered.
+// Naval armies can also cross water, boarding and leaving their ships on the coast.
+// Chains the paths between consecutive waypoints
+pub fn a_star_waypoints(
+    start: GameCoord,
+    waypoints: &[GameCoord],
+    tiles: &[Vec<Tile>],
+    naval: bool,
+) -> Option<VecDeque<GameCoord>> {
+    let mut path = VecDeque::from([start]);
+    let mut from = start;
+    for &to in waypoints {
+        let segment = a_star(from, to, tiles, naval)?;
+        path.extend(segment.into_iter().skip(1));
+        from = to;
+    }
+    Some(path)
+}
+
 pub fn a_star(
     start: GameCoord,
     end: GameCoord,
-    obstacles: &[Vec<bool>],
+    tiles: &[Vec<Tile>],
+    naval: bool,
 ) -> Option<VecDeque<GameCoord>> {
-    fn chebyshev(p1: GameCoord, p2: GameCoord) -> u16 {
-        p1.x.abs_diff(p2.x).max(p1.y.abs_diff(p2.y)) as u16
+    // Admissible as long as no step is cheaper than grass, diagonal steps included.
+    fn heuristic(p1: GameCoord, p2: GameCoord) -> u32 {
+        p1.x.abs_diff(p2.x).max(p1.y.abs_diff(p2.y)) as u32 * GRASS_MOVE_COST
     }
     fn trace_path(
         end_node: Node,
@@ -55,30 +88,53 @@ pub fn a_star(
         path.push_front(curr_node.coord);
         path
     }
+    fn get_tile(tiles: &[Vec<Tile>], coord: GameCoord) -> Tile {
+        tiles
+            .get(coord.y)
+            .and_then(|row| row.get(coord.x))
+            .copied()
+            .unwrap_or(Tile::Err)
+    }
 
-    if obstacles[start.y][start.x] || obstacles[end.y][end.x] {
-        return None;
+    // Armies arrive on land. They leave from land too, unless they are a fleet
+    // halted at sea.
+    let start_tile = get_tile(tiles, start);
+    if !(naval && start_tile == Tile::Water) {
+        start_tile.move_cost()?;
     }
+    get_tile(tiles, end).move_cost()?;
 
     let mut open_ord_list = BinaryHeap::new();
     let mut open_list = HashMap::new();
-    let mut closed_list = HashMap::new();
+    let mut closed_list: HashMap<GameCoord, Node> = HashMap::new();
     let start_node = Node {
         coord: start,
         prev: None,
         g: 0,
-        f: chebyshev(start, end),
+        f: heuristic(start, end),
     };
 
     open_ord_list.push(Reverse(start_node.clone()));
     open_list.insert(start, start_node);
 
-    while !open_ord_list.is_empty() {
-        let Reverse(current) = open_ord_list.pop().unwrap();
+    while let Some(Reverse(current)) = open_ord_list.pop() {
         let current_coord = current.coord;
+
+        // A cheaper node for this coord was already expanded
+        if let Some(node) = closed_list.get(&current_coord)
+            && node.g <= current.g
+        {
+            continue;
+        }
         let _ = open_list.remove(&current_coord);
 
+        // With weighted steps the path is optimal only once the end is popped
+        if current_coord == end {
+            return Some(trace_path(current, &mut closed_list));
+        }
+
         let current_g = current.g;
+        let current_tile = get_tile(tiles, current_coord);
         closed_list.insert(current_coord, current);
         for new_x in
             current_coord.x.saturating_sub(1)..=current_coord.x.saturating_add(1).min(MAP_COLS - 1)
@@ -90,25 +146,24 @@ pub fn a_star(
                 if new_coord == current_coord {
                     continue;
                 }
-                if obstacles[new_coord.y][new_coord.x] {
+                let Some(step_cost) = step_cost(current_tile, get_tile(tiles, new_coord), naval)
+                else {
                     continue;
-                }
+                };
+                let new_g = current_g + step_cost;
                 let new_node = Node {
                     coord: new_coord,
                     prev: Some(current_coord),
-                    g: current_g + 1,
-                    f: chebyshev(new_coord, end) + current_g + 1,
+                    g: new_g,
+                    f: heuristic(new_coord, end) + new_g,
                 };
-                if new_coord == end {
-                    return Some(trace_path(new_node, &mut closed_list));
-                }
                 if let Some(node) = open_list.get(&new_coord)
-                    && *node <= new_node
+                    && node.g <= new_node.g
                 {
                     continue;
                 }
                 if let Some(node) = closed_list.get(&new_coord)
-                    && *node <= new_node
+                    && node.g <= new_node.g
                 {
                     continue;
                 }
@@ -225,3 +280,92 @@ pub fn bds<const M: usize, const N: usize>(
     }
     Some(path)
 }
+
+#[cfg(test)]
+mod tests {
+    use common::r#const::WOODS_MOVE_COST;
+
+    use super::*;
+
+    // Open sea on the left, a coast on the right
+    fn coast() -> Vec<Vec<Tile>> {
+        vec![
+            vec![
+                Tile::Water,
+                Tile::Water,
+                Tile::Water,
+                Tile::Grass,
+                Tile::Grass
+            ];
+            3
+        ]
+    }
+
+    #[test]
+    fn fleet_routes_from_water() {
+        let tiles = coast();
+        let start = GameCoord::new(1, 0);
+        let end = GameCoord::new(1, 4);
+
+        let path = a_star(start, end, &tiles, true).expect("the fleet should find a route");
+        assert_eq!(path.front(), Some(&start));
+        assert_eq!(path.back(), Some(&end));
+    }
+
+    #[test]
+    fn land_army_cannot_route_from_water() {
+        let tiles = coast();
+        let start = GameCoord::new(1, 0);
+        let end = GameCoord::new(1, 4);
+
+        assert!(a_star(start, end, &tiles, false).is_none());
+    }
+
+    fn path_cost(path: &VecDeque<GameCoord>, tiles: &[Vec<Tile>]) -> u32 {
+        path.iter()
+            .zip(path.iter().skip(1))
+            .map(|(from, to)| {
+                step_cost(tiles[from.y][from.x], tiles[to.y][to.x], false)
+                    .expect("the path should only cross walkable tiles")
+            })
+            .sum()
+    }
+
+    #[test]
+    fn army_goes_around_woods_when_cheaper() {
+        use Tile::{Grass, Woods};
+        // A strip of woods between the start and the end, grass around it
+        let tiles = vec![
+            vec![Grass, Grass, Grass, Grass, Grass],
+            vec![Grass, Woods, Woods, Woods, Grass],
+            vec![Grass, Grass, Grass, Grass, Grass],
+        ];
+        let start = GameCoord::new(1, 0);
+        let end = GameCoord::new(1, 4);
+
+        let path = a_star(start, end, &tiles, false).expect("the army should find a route");
+        assert!(path.iter().all(|coord| tiles[coord.y][coord.x] == Grass));
+        // Four grass steps beat three woods and one grass step
+        assert_eq!(path_cost(&path, &tiles), 4 * GRASS_MOVE_COST);
+    }
+
+    #[test]
+    fn army_crosses_woods_when_cheaper_than_mountains() {
+        use Tile::{Grass, Mountain, Woods};
+        // The only gap in a ridge of mountains is covered by woods
+        let tiles = vec![
+            vec![Grass, Grass, Mountain, Grass, Grass],
+            vec![Grass, Grass, Woods, Grass, Grass],
+            vec![Grass, Grass, Mountain, Grass, Grass],
+        ];
+        let start = GameCoord::new(1, 0);
+        let end = GameCoord::new(1, 4);
+
+        let path = a_star(start, end, &tiles, false).expect("the army should find a route");
+        assert!(path.contains(&GameCoord::new(1, 2)));
+        assert_eq!(
+            path_cost(&path, &tiles),
+            3 * GRASS_MOVE_COST + WOODS_MOVE_COST
+        );
+    }
+}
@@ -0,0 +1,69 @@
+use std::collections::VecDeque;
+
+use common::{
+    packets::Rejection,
+    research::{ResearchJob, TechType},
+};
+
+use crate::r#const::RESEARCH_QUEUE_LEN;
+
+// Techs of a castle, the ones researched and the ones waiting in the queue
+pub struct Research {
+    researched: Vec<TechType>,
+    queue: VecDeque<ResearchJob>,
+}
+
+impl Research {
+    pub fn new() -> Self {
+        Self {
+            researched: Vec::new(),
+            queue: VecDeque::new(),
+        }
+    }
+
+    // Resources are paid by the castle. Prerequisites can still be in the queue.
+    pub fn queue_tech(&mut self, tech: TechType) -> Result<(), Rejection> {
+        if self.is_known(tech) {
+            return Err(Rejection::AlreadyResearched);
+        }
+        if !tech
+            .prerequisites()
+            .iter()
+            .all(|prerequisite| self.is_known(*prerequisite))
+        {
+            return Err(Rejection::MissingPrerequisite);
+        }
+        if self.queue.len() >= RESEARCH_QUEUE_LEN {
+            return Err(Rejection::ResearchQueueFull);
+        }
+
+        self.queue.push_back(ResearchJob::new(tech));
+        Ok(())
+    }
+
+    // Returns the tech researched on this tick
+    pub fn update(&mut self) -> Option<TechType> {
+        let job = self.queue.front_mut()?;
+        job.ticks_left = job.ticks_left.saturating_sub(1);
+        if job.ticks_left > 0 {
+            return None;
+        }
+
+        let tech = self.queue.pop_front()?.tech;
+        self.researched.push(tech);
+        Some(tech)
+    }
+
+    // Researched or queued
+    fn is_known(&self, tech: TechType) -> bool {
+        self.researched.contains(&tech) || self.queue.iter().any(|job| job.tech == tech)
+    }
+
+    pub fn get_researched(&self) -> &[TechType] {
+        &self.researched
+    }
+
+    pub fn export_queue(&self) -> Vec<ResearchJob> {
+        self.queue.iter().cloned().collect()
+    }
+}
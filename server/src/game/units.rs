@@ -1,77 +1,229 @@
 use std::collections::VecDeque;
 
-use common::{GameCoord, GameId, game_objs::DeployedUnitsE, units::UnitGroup};
+use common::{
+    GameCoord, GameId,
+    game_objs::{DeployedUnitsE, UnitsStatus},
+    map::Tile,
+    resources::Resources,
+    units::UnitGroup,
+};
+
+use crate::{
+    r#const::DISENGAGE_TICKS,
+    game::{map::Map, pathfinding},
+};
 
 pub enum DeployedUnitsEvent {
     AtDest,
     AtHome,
 }
 
+// What the units do once they reach the end of their path
+#[derive(Clone, Copy, PartialEq)]
+pub enum Order {
+    Attack(GameId),
+    // Join the garrison of the castle
+    Reinforce(GameId),
+    Move,
+    Return,
+}
+
+pub struct Route {
+    // Starts from the position the route was requested at
+    pub path: VecDeque<GameCoord>,
+    pub waypoints: VecDeque<GameCoord>,
+    // Path from the last waypoint back through all of them, walked over and over
+    pub patrol: Option<VecDeque<GameCoord>>,
+}
+
 #[derive(Clone)]
 pub struct DeployedUnits {
     unit_group: UnitGroup,
     owner_id: GameId,
-    target_id: Option<GameId>,
-    returning: bool,
+    order: Order,
+    pos: GameCoord,
+    // Tiles still to walk. None while the first route is being computed.
     path: Option<VecDeque<GameCoord>>,
-    path_index: usize,
-    path_size: usize,
+    // A route is being computed
+    planning: bool,
+    // Castle already warned of the attack of these units
+    warned_id: Option<GameId>,
+    waypoints: VecDeque<GameCoord>,
+    patrol: Option<VecDeque<GameCoord>>,
+    // Hundredths of a tick saved up to enter the next tile
+    move_progress: u32,
+    embarked: bool,
+    // Resources plundered, delivered once back home
+    loot: Resources,
+    // Ticks left before the units can fight another field battle
+    disengage_ticks: u32,
 }
 
 impl DeployedUnits {
-    pub fn new(
-        owner_id: GameId,
-        target_id: Option<GameId>,
-        path: Option<VecDeque<GameCoord>>,
-        unit_group: UnitGroup,
-    ) -> Self {
-        let path_size = path.as_ref().map(|path| path.len()).unwrap_or(0);
-
+    pub fn new(owner_id: GameId, order: Order, pos: GameCoord, unit_group: UnitGroup) -> Self {
         Self {
             owner_id,
-            target_id,
-            path_size,
-            path,
+            order,
+            pos,
+            path: None,
+            planning: false,
+            warned_id: None,
+            waypoints: VecDeque::new(),
+            patrol: None,
             unit_group,
-            path_index: 0,
-            returning: false,
+            move_progress: 0,
+            embarked: false,
+            loot: Resources::new(),
+            disengage_ticks: 0,
         }
     }
 
-    pub fn get_pos(&self) -> Option<GameCoord> {
-        self.path.as_ref().map(|path| path[self.path_index])
+    pub fn get_pos(&self) -> GameCoord {
+        self.pos
     }
 
-    pub fn step(&mut self) -> Option<DeployedUnitsEvent> {
-        if self.path.is_none() {
+    // The owner techs scale the ticks spent on each tile. The progress is kept
+    // in hundredths of a tick, so that a faster army can enter more than one
+    // tile in a tick.
+    pub fn step(&mut self, map: &Map, move_cost_percent: u32) -> Option<DeployedUnitsEvent> {
+        self.disengage_ticks = self.disengage_ticks.saturating_sub(1);
+        if self.path.as_ref().is_none_or(|path| path.is_empty()) {
+            // Units holding their position don't save up movement
+            self.move_progress = 0;
             return None;
-        };
+        }
+        self.move_progress += 100;
 
-        self.path_index = match self.returning {
-            true => self.path_index - 1,
-            false => self.path_index + 1,
-        };
+        loop {
+            let path = self.path.as_mut()?;
+            let next_pos = *path.front()?;
+            let prev_tile = map.get_tile(self.pos).unwrap_or(Tile::Err);
+            let tile = map.get_tile(next_pos).unwrap_or(Tile::Err);
+            let step_cost =
+                pathfinding::step_cost(prev_tile, tile, self.unit_group.can_sail()).unwrap_or(1);
+            let cost = (step_cost * move_cost_percent).max(1);
+            if self.move_progress < cost {
+                return None;
+            }
+            self.move_progress -= cost;
 
-        if self.path_index == self.path_size - 1 {
-            self.returning = true;
-            return Some(DeployedUnitsEvent::AtDest);
-        };
+            path.pop_front();
+            self.pos = next_pos;
+            self.embarked = tile == Tile::Water;
 
-        if self.path_index == 0 {
-            return Some(DeployedUnitsEvent::AtHome);
-        };
+            if self.waypoints.front() == Some(&next_pos) {
+                let reached = self.waypoints.pop_front();
+                if self.patrol.is_some() {
+                    self.waypoints.extend(reached);
+                }
+            }
 
-        None
+            if !path.is_empty() {
+                continue;
+            }
+            if let Some(ref patrol) = self.patrol {
+                path.extend(patrol.iter().skip(1));
+                continue;
+            }
+            self.move_progress = 0;
+            return match self.order {
+                Order::Return => Some(DeployedUnitsEvent::AtHome),
+                _ => Some(DeployedUnitsEvent::AtDest),
+            };
+        }
     }
 
-    // This needs to be fully rethinked because i want dynamic battles!
-    pub fn get_strength(&self) -> u32 {
-        self.unit_group.get_strength()
+    pub fn set_route(&mut self, order: Order, route: Route) {
+        let Route {
+            mut path,
+            waypoints,
+            patrol,
+        } = route;
+        if path.len() > 1 {
+            path.pop_front();
+        }
+        self.order = order;
+        self.path = Some(path);
+        self.planning = false;
+        self.waypoints = waypoints;
+        // Patrolling a single tile is just holding it
+        self.patrol = patrol.filter(|patrol| patrol.len() > 1);
     }
 
-    pub fn set_path(&mut self, path: VecDeque<GameCoord>) {
-        self.path_size = path.len();
-        self.path = Some(path);
+    // Stops on the current tile, keeping the orders, until a new route is set
+    pub fn halt(&mut self) {
+        if let Some(ref mut path) = self.path {
+            path.clear();
+        }
+        self.waypoints.clear();
+        self.patrol = None;
+        self.planning = true;
+    }
+
+    // No route was found, the units keep holding their position
+    pub fn stop_planning(&mut self) {
+        self.planning = false;
+    }
+
+    // Retreating units are left alone for a while, so that they can get away
+    pub fn disengage(&mut self) {
+        self.disengage_ticks = DISENGAGE_TICKS;
+    }
+
+    pub fn is_disengaged(&self) -> bool {
+        self.disengage_ticks > 0
+    }
+
+    pub fn has_route(&self) -> bool {
+        self.path.is_some()
+    }
+
+    // Other units join these ones, keeping these orders
+    pub fn merge(&mut self, other: DeployedUnits) -> bool {
+        let mut merged = self.unit_group.clone();
+        merged.saturating_add(&other.unit_group);
+        if (self.embarked || other.embarked) && !merged.can_sail() {
+            return false;
+        }
+        self.unit_group = merged;
+        self.loot.saturating_add(&other.loot);
+        true
+    }
+
+    // Detaches part of the units, which hold the current position
+    pub fn split(&mut self, unit_group: &UnitGroup) -> Option<DeployedUnits> {
+        let mut remaining = self.unit_group.clone();
+        if unit_group.is_empty() || !remaining.subtract_if_enough(unit_group) {
+            return None;
+        }
+        if remaining.is_empty() {
+            return None;
+        }
+        if self.embarked && !(remaining.can_sail() && unit_group.can_sail()) {
+            return None;
+        }
+        self.unit_group = remaining;
+
+        Some(DeployedUnits {
+            unit_group: unit_group.clone(),
+            owner_id: self.owner_id,
+            order: Order::Move,
+            pos: self.pos,
+            path: Some(VecDeque::new()),
+            planning: false,
+            warned_id: None,
+            waypoints: VecDeque::new(),
+            patrol: None,
+            move_progress: self.move_progress,
+            embarked: self.embarked,
+            // The loot stays with the main group
+            loot: Resources::new(),
+            disengage_ticks: self.disengage_ticks,
+        })
+    }
+
+    pub fn is_embarked(&self) -> bool {
+        self.embarked
     }
 
     pub fn get_owner_id(&self) -> GameId {
@@ -82,18 +234,105 @@ impl DeployedUnits {
         &self.unit_group
     }
 
-    pub fn get_target(&self) -> Option<GameId> {
-        self.target_id
+    pub fn get_unit_group_mut(&mut self) -> &mut UnitGroup {
+        &mut self.unit_group
+    }
+
+    // Capacity left after the loot already carried
+    pub fn get_free_capacity(&self) -> u32 {
+        self.unit_group
+            .get_carry_capacity()
+            .saturating_sub(self.loot.total())
+    }
+
+    pub fn add_loot(&mut self, loot: &Resources) {
+        self.loot.saturating_add(loot);
+    }
+
+    pub fn get_loot(&self) -> &Resources {
+        &self.loot
+    }
+
+    pub fn is_warned(&self, castle_id: GameId) -> bool {
+        self.warned_id == Some(castle_id)
+    }
+
+    pub fn set_warned(&mut self, castle_id: GameId) {
+        self.warned_id = Some(castle_id);
+    }
+
+    pub fn get_order(&self) -> Order {
+        self.order
+    }
+
+    // Units with an empty path hold their position
+    pub fn get_status(&self) -> UnitsStatus {
+        match (&self.path, self.order) {
+            _ if self.planning => UnitsStatus::PlanningRoute,
+            _ if self.patrol.is_some() => UnitsStatus::Patrolling,
+            (Some(path), _) if path.is_empty() => UnitsStatus::Stationed,
+            (_, Order::Return) => UnitsStatus::Returning,
+            _ => UnitsStatus::Marching,
+        }
     }
 
-    pub fn export(&self) -> Option<DeployedUnitsE> {
-        let Some(pos) = self.get_pos() else {
+    // The unit count and the route are visible only to the owner,
+    // as are the units still in the castle waiting for their first route
+    pub fn export(&self, viewer_id: Option<GameId>) -> Option<DeployedUnitsE> {
+        let owned = viewer_id == Some(self.owner_id);
+        if self.path.is_none() && !owned {
             return None;
-        };
+        }
 
         Some(DeployedUnitsE {
             owner_id: self.owner_id,
-            pos,
+            pos: self.pos,
+            embarked: self.embarked,
+            status: self.get_status(),
+            units: owned.then(|| self.unit_group.clone()),
+            waypoints: owned.then(|| self.waypoints.iter().copied().collect()),
+            loot: owned.then(|| self.loot.clone()),
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use common::{
+        research::{self, TechType},
+        units::UnitType,
+    };
+
+    use super::*;
+
+    // Ticks a knight takes to walk a straight line of grass
+    fn ticks_to_cross_grass(tiles: usize, move_cost_percent: u32) -> u32 {
+        let map = Map::from_tiles(vec![vec![Tile::Grass; tiles + 1]]);
+        let mut knight = UnitGroup::new();
+        knight.add_single_type(UnitType::Knight, 1);
+        let mut units = DeployedUnits::new(0, Order::Move, GameCoord::new(0, 0), knight);
+        let path = (0..=tiles).map(|x| GameCoord::new(0, x)).collect();
+        units.set_route(
+            Order::Move,
+            Route {
+                path,
+                waypoints: VecDeque::new(),
+                patrol: None,
+            },
+        );
+
+        let mut ticks = 1;
+        while units.step(&map, move_cost_percent).is_none() {
+            ticks += 1;
+        }
+        ticks
+    }
+
+    #[test]
+    fn roads_speed_up_the_march_on_grass() {
+        let move_cost_percent = research::move_cost_percent(&[TechType::Roads]);
+
+        assert_eq!(ticks_to_cross_grass(12, 100), 12);
+        assert_eq!(ticks_to_cross_grass(12, move_cost_percent), 9);
+    }
+}
@@ -7,12 +7,13 @@ use std::{
 
 use common::{
     r#const::MAX_LOBBY_PLAYERS,
-    packets::{C2S4L, CourtyardPacket, L2S4C, LogE, MainPacket},
+    packets::{C2S4L, CourtyardPacket, L2S4C, MainPacket, Rejection, RequestId},
 };
 
 use crate::{
+    audit,
     r#const::{GAME_TICK, LOBBY_POOL_LEN},
-    game::game::Game,
+    game::{game::Game, kit::StartingKit},
     player::Player,
     server::{Client, ClientId, S2L},
     thread_pool::ThreadPool,
@@ -20,7 +21,7 @@ use crate::{
 
 struct ClientCh {
     tx: Sender<L2S4C>,
-    rx: Receiver<C2S4L>,
+    rx: Receiver<(RequestId, C2S4L)>,
 }
 
 pub struct Lobby {
@@ -30,10 +31,12 @@ pub struct Lobby {
     num_players: usize,
     game: Option<Game>,
     pool: ThreadPool,
+    // Given to every new castle, set when the lobby is created
+    starting_kit: &'static StartingKit,
 }
 
 impl Lobby {
-    pub fn new(id: usize) -> Self {
+    pub fn new(id: usize, starting_kit: &'static StartingKit) -> Self {
         Self {
             id,
             players: HashMap::new(),
@@ -41,6 +44,7 @@ impl Lobby {
             num_players: 0,
             game: None,
             pool: ThreadPool::new(LOBBY_POOL_LEN),
+            starting_kit,
         }
     }
 
@@ -58,7 +62,11 @@ impl Lobby {
             self.listen_server(&mut main_rx, &mut running);
             self.listen_clients();
 
-            let dead_castles = self.game.as_mut().map(Game::step).unwrap_or_default();
+            let dead_castles = self
+                .game
+                .as_mut()
+                .map(|game| game.step(&self.pool))
+                .unwrap_or_default();
             for dead_castle in dead_castles.iter() {
                 if let Some((_, player)) = self
                     .players
@@ -68,6 +76,7 @@ impl Lobby {
                     player.castle_id = None;
                 }
             }
+            self.send_events();
 
             self.send_updates();
 
@@ -144,58 +153,97 @@ impl Lobby {
                 continue;
             };
 
-            if let Ok(msg) = client_ch.rx.try_recv() {
-                let mut log = None;
-                match msg {
+            if let Ok((request_id, msg)) = client_ch.rx.try_recv() {
+                let castle_id = player.castle_id.ok_or(Rejection::NoCastle);
+                let result = match msg {
                     C2S4L::NewCastle(pos) => {
                         println!("Client ({}) requested to build a new castle", client_id);
-                        if player.castle_id.is_none()
-                            && let Some(castle_id) =
-                                game.add_player_castle(player.name.clone(), pos)
-                        {
-                            player.set_castle_id(castle_id);
+                        if player.castle_id.is_some() {
+                            Err(Rejection::AlreadyHasCastle)
                         } else {
-                            log = Some(LogE::CastleCreationErr);
+                            game.add_player_castle(player.name.clone(), pos, self.starting_kit)
+                                .map(|castle_id| player.set_castle_id(castle_id))
                         }
                     }
-                    C2S4L::AttackCastle(target_id, unit_group_e) => {
-                        if let Some(castle_id) = player.castle_id
-                            && !game.attack_castle(castle_id, target_id, unit_group_e, &self.pool)
-                        {
-                            log = Some(LogE::AttackDeployErr);
-                        }
+                    C2S4L::AttackCastle(target_id, unit_group_e) => castle_id
+                        .and_then(|id| game.attack_castle(id, target_id, unit_group_e, &self.pool)),
+                    C2S4L::SendUnits(waypoints, unit_group_e, patrol) => castle_id.and_then(|id| {
+                        game.request_send_units(
+                            id,
+                            waypoints,
+                            patrol,
+                            unit_group_e,
+                            None,
+                            &self.pool,
+                        )
+                    }),
+                    C2S4L::RecallUnits(units_id) => {
+                        castle_id.and_then(|id| game.recall_units(id, units_id, &self.pool))
                     }
-                    C2S4L::SendUnits(target_pos, unit_group_e) => {
-                        if let Some(castle_id) = player.castle_id
-                            && !game.request_send_units(
-                                castle_id,
-                                target_pos,
-                                unit_group_e,
-                                None,
-                                &self.pool,
-                            )
-                        {
-                            log = Some(LogE::UnitDeployErr);
-                        }
+                    C2S4L::RedirectUnits(units_id, target_pos) => castle_id.and_then(|id| {
+                        game.redirect_units(id, units_id, target_pos, None, &self.pool)
+                    }),
+                    C2S4L::RedirectAttack(units_id, target_id) => castle_id
+                        .and_then(|id| game.redirect_attack(id, units_id, target_id, &self.pool)),
+                    C2S4L::MergeUnits(units_id, into_id) => {
+                        castle_id.and_then(|id| game.merge_units(id, units_id, into_id))
+                    }
+                    C2S4L::SplitUnits(units_id, unit_group) => castle_id
+                        .and_then(|id| game.split_units(id, units_id, unit_group))
+                        .map(|_| ()),
+                    C2S4L::ReinforceCastle(target_id, unit_group) => castle_id.and_then(|id| {
+                        game.reinforce_castle(id, target_id, unit_group, &self.pool)
+                    }),
+                    C2S4L::RecallReinforcements(target_id) => castle_id
+                        .and_then(|id| game.recall_reinforcements(id, target_id, &self.pool)),
+                    C2S4L::GiftResources(target_id, resources) => {
+                        castle_id.and_then(|id| game.gift_resources(id, target_id, resources))
                     }
                     C2S4L::InCourtyard => {
                         player.in_courtyard = true;
+                        Ok(())
                     }
                     C2S4L::OutCourtyard => {
                         player.in_courtyard = false;
+                        Ok(())
                     }
                     C2S4L::NewFacility((pos, facility_type)) => {
-                        let Some(castle_id) = player.castle_id else {
-                            continue;
+                        castle_id.and_then(|id| game.add_facility(id, facility_type, pos))
+                    }
+                    C2S4L::UpgradeFacility(facility_id) => {
+                        castle_id.and_then(|id| game.upgrade_facility(id, facility_id))
+                    }
+                    C2S4L::DemolishFacility(facility_id) => {
+                        castle_id.and_then(|id| game.demolish_facility(id, facility_id))
+                    }
+                    C2S4L::RelocateFacility(facility_id, pos) => {
+                        castle_id.and_then(|id| game.relocate_facility(id, facility_id, pos))
+                    }
+                    C2S4L::UpgradeCastle => castle_id.and_then(|id| game.upgrade_castle(id)),
+                    C2S4L::Research(tech) => castle_id.and_then(|id| game.research(id, tech)),
+                    C2S4L::AssignWorkers(facility_id, workers) => {
+                        castle_id.and_then(|id| game.assign_workers(id, facility_id, workers))
+                    }
+                    C2S4L::TrainUnits(unit, count) => {
+                        castle_id.and_then(|id| game.train_units(id, unit, count))
+                    }
+                    C2S4L::CancelTraining(job_id) => {
+                        castle_id.and_then(|id| game.cancel_training(id, job_id))
+                    }
+                    C2S4L::GrantKit(target_id, kit_name) => {
+                        let result = if player.client.is_admin {
+                            game.grant_kit(target_id, &kit_name)
+                        } else {
+                            Err(Rejection::NotAdmin)
                         };
-                        if !game.add_facility(castle_id, facility_type, pos) {
-                            log = Some(LogE::FacilityCreationErr);
-                        }
+                        // Names are chosen by the clients, the id is not
+                        let admin = format!("{} (client {})", player.name, client_id);
+                        let command = format!("grant_kit {} {}", target_id, kit_name);
+                        audit::log_admin_command(&admin, &command, &result);
+                        result
                     }
-                }
-                if let Some(log) = log {
-                    let _ = client_ch.tx.send(L2S4C::Log(log));
-                }
+                };
+                let _ = client_ch.tx.send(L2S4C::Feedback(request_id, result));
             }
         }
     }
@@ -216,19 +264,36 @@ impl Lobby {
         }
     }
 
+    fn send_events(&mut self) {
+        let Some(game) = self.game.as_mut() else {
+            return;
+        };
+        for (castle_id, event) in game.take_events() {
+            let Some((client_id, _)) = self
+                .players
+                .iter()
+                .find(|(_, player)| player.castle_id == Some(castle_id))
+            else {
+                continue;
+            };
+            if let Some(client_ch) = self.clients_ch.get(client_id) {
+                let _ = client_ch.tx.send(L2S4C::Event(event));
+            }
+        }
+    }
+
     fn send_map(client_ch: &ClientCh, game: &Game) {
         let _ = client_ch.tx.send(L2S4C::Map(game.export_map()));
     }
 
     fn send_main_packet(client_ch: &ClientCh, player: &Player, game: &Game) {
-        let castle_export = player.castle_id.and_then(|castle_id| {
-            game.get_castle(castle_id)
-                .map(|castle| castle.export_owned())
-        });
+        let castle_export = player
+            .castle_id
+            .and_then(|castle_id| game.export_owned_castle(castle_id));
 
         let packet = MainPacket {
             time: game.get_time(),
-            objs: game.export_objs(),
+            objs: game.export_objs(player.castle_id),
             player: player.export(),
             castle: castle_export,
         };
@@ -243,12 +308,18 @@ impl Lobby {
         let Some(castle) = game.get_castle(castle_id) else {
             return;
         };
+        let Some(castle_e) = game.export_owned_castle(castle_id) else {
+            return;
+        };
 
         let packet = CourtyardPacket {
             time: game.get_time(),
             player: player.export(),
-            castle: castle.export_owned(),
+            castle: castle_e,
             facilities: castle.export_courtyard(),
+            build_queue: castle.export_build_queue(),
+            training_queue: castle.export_training_queue(),
+            peasants: castle.get_peasants(),
         };
 
         let _ = client_ch.tx.send(L2S4C::CourtyardPacket(packet));
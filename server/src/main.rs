@@ -1,3 +1,4 @@
+mod audit;
 mod connection;
 mod r#const;
 mod game;
@@ -1,19 +1,26 @@
 use std::{
+    env,
     net::{TcpListener, TcpStream},
     sync::mpsc::{self, Receiver, Sender},
     time::{Duration, Instant},
 };
 
-use crate::{connection::Connection, r#const::SERVER_TICK, lobby::Lobby, thread_pool::ThreadPool};
+use crate::{
+    connection::Connection,
+    r#const::{DEFAULT_KIT, SERVER_TICK},
+    game::kit::StartingKit,
+    lobby::Lobby,
+    thread_pool::ThreadPool,
+};
 use common::{
-    r#const::{IP_LOCAL, MAX_LOBBIES},
-    packets::{C2S, C2S4L, L2S4C, S2C},
+    r#const::{ADMIN_TOKEN_VAR, IP_LOCAL, MAX_LOBBIES},
+    packets::{C2S, C2S4L, L2S4C, RequestId, S2C},
     stream::StreamErr,
 };
 
 pub enum S2L {
     IsFull(Sender<bool>),
-    NewClient(Client, Sender<L2S4C>, Receiver<C2S4L>),
+    NewClient(Client, Sender<L2S4C>, Receiver<(RequestId, C2S4L)>),
     Disconnection(ClientId),
     Shutdown,
 }
@@ -26,20 +33,24 @@ pub enum ServerErr {
 
 pub type ClientId = usize;
 pub type ConnId = usize;
+pub type LobbyLink = (Sender<(RequestId, C2S4L)>, Receiver<L2S4C>);
 
 #[derive(Clone)]
 pub struct Client {
     pub id: ClientId,
     pub name: String,
     pub lobby: Option<usize>,
+    // Logged in with the admin token of the server
+    pub is_admin: bool,
 }
 
 impl Client {
-    pub fn new(id: ClientId, name: String) -> Self {
+    pub fn new(id: ClientId, name: String, is_admin: bool) -> Self {
         Self {
             id,
             name,
             lobby: None,
+            is_admin,
         }
     }
 }
@@ -49,6 +60,8 @@ pub struct Server {
     txs: [Sender<S2L>; MAX_LOBBIES],
     conns: Vec<Connection>,
     conn_id_cnt: ConnId,
+    // Read from ADMIN_TOKEN_VAR on start, admin commands are disabled without it
+    admin_token: Option<String>,
 }
 
 impl Server {
@@ -61,7 +74,9 @@ impl Server {
             txs.push(tx);
 
             pool.execute(move || {
-                let lobby = Lobby::new(lobby_id);
+                let starting_kit =
+                    StartingKit::find(DEFAULT_KIT).expect("the default kit must exist");
+                let lobby = Lobby::new(lobby_id, starting_kit);
                 lobby.run(rx);
             });
         }
@@ -71,6 +86,9 @@ impl Server {
             txs: txs.try_into().unwrap(),
             conns: Vec::new(),
             conn_id_cnt: 0,
+            admin_token: env::var(ADMIN_TOKEN_VAR)
+                .ok()
+                .filter(|token| !token.is_empty()),
         }
     }
 
@@ -95,16 +113,17 @@ impl Server {
 
             for (i, ref mut conn) in self.conns.iter_mut().enumerate() {
                 match conn.try_get_msg() {
-                    Ok(Some(C2S::C2S4L(msg))) => {
+                    Ok(Some(C2S::C2S4L(request_id, msg))) => {
                         let Some(ref mut lobby_link) = conn.lobby_link else {
                             continue;
                         };
-                        if lobby_link.0.send(msg).is_err() {
+                        if lobby_link.0.send((request_id, msg)).is_err() {
                             println!("[server] Failed...");
                         }
                     }
-                    Ok(Some(C2S::Login(user_name))) => {
-                        conn.client = Some(Client::new(conn.id, user_name));
+                    Ok(Some(C2S::Login(user_name, token))) => {
+                        let is_admin = self.admin_token.is_some() && token == self.admin_token;
+                        conn.client = Some(Client::new(conn.id, user_name, is_admin));
                         println!("User authenticated");
                     }
                     Ok(Some(C2S::Lobby(lobby_id))) => {
@@ -197,7 +216,7 @@ impl Server {
         lobby_id: usize,
         lobby_tx: &Sender<S2L>,
         client: &mut Client,
-    ) -> Result<(Sender<C2S4L>, Receiver<L2S4C>), ServerErr> {
+    ) -> Result<LobbyLink, ServerErr> {
         let (resp_tx, resp_rx) = mpsc::channel();
         let _ = lobby_tx.send(S2L::IsFull(resp_tx));
 